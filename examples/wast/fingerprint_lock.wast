@@ -0,0 +1,25 @@
+;; SPDX-License-Identifier: FSL-1.1
+(module
+  ;; importing the wacc functions
+  (import "wacc" "_check_fingerprint" (func $check_fingerprint (param i32 i32 i32 i32) (result i32)))
+
+  ;; function to check that the proposed public key matches the stored fingerprint
+  (func $main (export "move_every_zig") (param) (result i32)
+    ;; check_fingerprint("/entry/pubkey", "/fingerprint")
+    i32.const 0
+    i32.const 13
+    i32.const 13
+    i32.const 12
+    call $check_fingerprint
+    return
+  )
+
+  ;; export the memory
+  (memory (export "memory") 1)
+
+  ;; String constants for referenceing key-value pairs
+  ;;
+  ;;                    [NAME]                [IDX] [LEN]
+  (data (i32.const  0)  "/entry/pubkey" )  ;;     0    13
+  (data (i32.const 13)  "/fingerprint"  )  ;;    13    12
+)
@@ -0,0 +1,14 @@
+;; SPDX-License-Identifier: FSL-1.1
+(module
+  ;; a script that uses floating point instructions
+  (func $main (export "move_every_zig") (param) (result i32)
+    ;; 1.0 < 2.0
+    f32.const 1.0
+    f32.const 2.0
+    f32.lt
+    return
+  )
+
+  ;; export the memory
+  (memory (export "memory") 1)
+)
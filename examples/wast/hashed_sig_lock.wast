@@ -0,0 +1,26 @@
+;; SPDX-License-Identifier: FSL-1.1
+(module
+  ;; importing the wacc functions
+  (import "wacc" "_check_signature_hashed" (func $check_signature_hashed (param i32 i32 i32 i32 i32) (result i32)))
+
+  ;; function to check a pubkey signature over the sha2-256 multihash of the message
+  (func $main (export "move_every_zig") (param) (result i32)
+    ;; check_signature_hashed("/pubkey", "/entry/", sha2-256)
+    i32.const 7
+    i32.const 7
+    i32.const 0
+    i32.const 7
+    i32.const 0x12
+    call $check_signature_hashed
+    return
+  )
+
+  ;; export the memory
+  (memory (export "memory") 1)
+
+  ;; String constants for referenceing key-value pairs
+  ;;
+  ;;                    [NAME]          [IDX] [LEN]
+  (data (i32.const  0)  "/entry/" )  ;;     0     7
+  (data (i32.const  7)  "/pubkey" )  ;;     7     7
+)
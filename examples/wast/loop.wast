@@ -0,0 +1,14 @@
+;; SPDX-License-Identifier: FSL-1.1
+(module
+  ;; a script that never finishes, used to exhaust the fuel budget
+  (func $main (export "move_every_zig") (param) (result i32)
+    (loop $forever
+      br $forever
+    )
+    i32.const 1
+    return
+  )
+
+  ;; export the memory
+  (memory (export "memory") 1)
+)
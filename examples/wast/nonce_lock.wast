@@ -0,0 +1,25 @@
+;; SPDX-License-Identifier: FSL-1.1
+(module
+  ;; importing the wacc functions
+  (import "wacc" "_check_fresh_nonce" (func $check_fresh_nonce (param i32 i32 i32 i32) (result i32)))
+
+  ;; function to check that the proposed nonce hasn't been used before
+  (func $main (export "move_every_zig") (param) (result i32)
+    ;; check_fresh_nonce("/entry/nonce", "/nonces/")
+    i32.const 0
+    i32.const 12
+    i32.const 12
+    i32.const 8
+    call $check_fresh_nonce
+    return
+  )
+
+  ;; export the memory
+  (memory (export "memory") 1)
+
+  ;; String constants for referenceing key-value pairs
+  ;;
+  ;;                    [NAME]               [IDX] [LEN]
+  (data (i32.const  0)  "/entry/nonce" )  ;;     0    12
+  (data (i32.const 12)  "/nonces/"     )  ;;    12     8
+)
@@ -0,0 +1,24 @@
+;; SPDX-License-Identifier: FSL-1.1
+(module
+  ;; importing the wacc functions
+  (import "wacc" "_branch" (func $branch (param i32 i32) (result i32 i32)))
+  (import "wacc" "_check_preimage"  (func $check_preimage  (param i32 i32) (result i32)))
+
+  ;; function to check a preimage proof in the branch context
+  (func $main (export "move_every_zig") (param) (result i32)
+    ;; check_preimage(branch("hash"))
+    i32.const 0
+    i32.const 4
+    call $branch
+    call $check_preimage
+    return
+  )
+
+  ;; export the memory
+  (memory (export "memory") 1)
+
+  ;; String constants for referenceing key-value pairs
+  ;;
+  ;;                    [NAME]          [IDX] [LEN]
+  (data (i32.const 0)  "hash"     )  ;;     0     4
+)
@@ -1,33 +1,295 @@
 // SPDX-License-Identifier: FSL-1.1
 pub(crate) mod branch;
+pub(crate) mod check_aggregate_signature;
 pub(crate) mod check_eq;
+pub(crate) mod check_fingerprint;
+pub(crate) mod check_fresh_nonce;
 pub(crate) mod check_preimage;
 pub(crate) mod check_signature;
 pub(crate) mod log;
 pub(crate) mod push;
 
-use crate::{error::ApiError, Context, Error};
-use wasmtime::{AsContextMut, Caller, Engine, Extern, Linker, Val};
+use crate::{
+    error::ApiError,
+    storage::{Local, Storage, Threaded},
+    vm::Phase,
+    Context, Error,
+};
+use wasmtime::{AsContextMut, Caller, Engine, Extern, FuncType, Linker, Trap, Val, ValType};
 
 pub const WASM_TRUE: Val = Val::I32(1);
 pub const WASM_FALSE: Val = Val::I32(0);
 
-/// Add the API functions to the given Linker
-pub(crate) fn add_to_linker(engine: &Engine, linker: &mut Linker<Context<'_>>) -> Result<(), Error>
+/// How the API functions are registered with the linker
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Mode {
+    /// For engines without async support
+    Sync,
+    /// For engines with async support, the key-value pairs each function
+    /// reads are prefetched before it runs
+    Async,
+}
+
+/// The key-value pairs an API function reads, given its string parameters
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Prefetch {
+    /// Keys read from the current state
+    pub current_keys: Vec<String>,
+    /// Key-path prefixes read from the current state
+    pub current_prefixes: Vec<String>,
+    /// Keys read from the proposed state
+    pub proposed_keys: Vec<String>,
+    /// Key-path prefixes read from the proposed state
+    pub proposed_prefixes: Vec<String>,
+}
+
+/// Gets the string parameter at the index as a list of zero or one keys
+pub(crate) fn arg(args: &[String], idx: usize) -> Vec<String> {
+    args.get(idx).cloned().into_iter().collect()
+}
+
+/// The key-value pairs the API function reads, given its string parameters
+pub(crate) fn prefetch(name: &str, args: &[String]) -> Prefetch {
+    match name {
+        "_check_aggregate_signature" => check_aggregate_signature::prefetch(args),
+        "_check_eq" => check_eq::prefetch(args),
+        "_check_fingerprint" => check_fingerprint::prefetch(args),
+        "_check_fresh_nonce" => check_fresh_nonce::prefetch(args),
+        "_check_preimage" => check_preimage::prefetch(args),
+        "_check_signature" | "_check_signature_domain" | "_check_signature_hashed" => {
+            check_signature::prefetch(args)
+        }
+        "_push" => push::prefetch(args),
+        _ => Prefetch::default(),
+    }
+}
+
+/// For API functions that don't read any key-value pairs
+pub(crate) fn no_prefetch(_args: &[String]) -> Prefetch {
+    Prefetch::default()
+}
+
+/// The signature of the API function implementations
+pub(crate) type HostFn<M> = for<'a, 'b, 'c, 'd> fn(Caller<'a, Context<'b, M>>, &'c [Val], &'d mut [Val]) -> Result<(), wasmtime::Error>;
+
+const I32: wasmparser::ValType = wasmparser::ValType::I32;
+
+/// The parameter and result types of every API function. The functions are
+/// registered with these types and the validator checks the imports against
+/// them.
+const SIGNATURES: &[(&str, &[wasmparser::ValType], &[wasmparser::ValType])] = &[
+    ("_branch", &[I32, I32], &[I32, I32]),
+    ("_check_aggregate_signature", &[I32, I32, I32, I32], &[I32]),
+    ("_check_eq", &[I32, I32], &[I32]),
+    ("_check_fingerprint", &[I32, I32, I32, I32], &[I32]),
+    ("_check_fresh_nonce", &[I32, I32, I32, I32], &[I32]),
+    ("_check_preimage", &[I32, I32], &[I32]),
+    ("_check_signature", &[I32, I32, I32, I32], &[I32]),
+    ("_check_signature_domain", &[I32, I32, I32, I32], &[I32]),
+    ("_check_signature_hashed", &[I32, I32, I32, I32, I32], &[I32]),
+    ("_log", &[I32, I32], &[I32]),
+    ("_push", &[I32, I32], &[I32]),
+];
+
+/// Gets the parameter and result types of the API function
+pub(crate) fn signature(name: &str) -> Option<(&'static [wasmparser::ValType], &'static [wasmparser::ValType])> {
+    SIGNATURES
+        .iter()
+        .find(|(n, _, _)| *n == name)
+        .map(|(_, params, results)| (*params, *results))
+}
+
+/// Maps the wasmparser value type to the wasmtime one
+fn val_type(ty: wasmparser::ValType) -> Option<ValType> {
+    match ty {
+        wasmparser::ValType::I32 => Some(ValType::I32),
+        wasmparser::ValType::I64 => Some(ValType::I64),
+        wasmparser::ValType::F32 => Some(ValType::F32),
+        wasmparser::ValType::F64 => Some(ValType::F64),
+        wasmparser::ValType::V128 => Some(ValType::V128),
+        wasmparser::ValType::Ref(_) => None,
+    }
+}
+
+/// Add the API functions available in the phase to the given Linker
+pub(crate) fn add_to_linker<M: Registrar>(
+    engine: &Engine,
+    linker: &mut Linker<Context<'_, M>>,
+    mode: Mode,
+    phase: Phase,
+) -> Result<(), Error>
 {
-    branch::add_to_linker(engine, linker)?;
-    check_eq::add_to_linker(engine, linker)?;
-    check_preimage::add_to_linker(engine, linker)?;
-    check_signature::add_to_linker(engine, linker)?;
-    log::add_to_linker(engine, linker)?;
-    push::add_to_linker(engine, linker)?;
+    // available in every phase
+    branch::add_to_linker(engine, linker, mode)?;
+    log::add_to_linker(engine, linker, mode)?;
+
+    // data staging functions
+    if phase.stages() {
+        push::add_to_linker(engine, linker, mode)?;
+    }
+
+    // check functions
+    if phase.checks() {
+        check_aggregate_signature::add_to_linker(engine, linker, mode)?;
+        check_eq::add_to_linker(engine, linker, mode)?;
+        check_fingerprint::add_to_linker(engine, linker, mode)?;
+        check_fresh_nonce::add_to_linker(engine, linker, mode)?;
+        check_preimage::add_to_linker(engine, linker, mode)?;
+        check_signature::add_to_linker(engine, linker, mode)?;
+    }
+    Ok(())
+}
+
+/// Registers an API function with the linker, with its type from the
+/// signature table. In async mode the function awaits the prefetching of the
+/// key-value pairs it reads before it runs.
+pub(crate) fn func_new<M: Registrar>(
+    engine: &Engine,
+    linker: &mut Linker<Context<'_, M>>,
+    mode: Mode,
+    name: &str,
+    prefetch: fn(&[String]) -> Prefetch,
+    func: HostFn<M>,
+) -> Result<(), Error>
+{
+    let (params, results) = signature(name).ok_or_else(|| ApiError::RegisterApiFailed(format!("{name} has no signature")))?;
+    let params: Option<Vec<ValType>> = params.iter().copied().map(val_type).collect();
+    let results: Option<Vec<ValType>> = results.iter().copied().map(val_type).collect();
+    let ty = match (params, results) {
+        (Some(params), Some(results)) => FuncType::new(engine, params, results),
+        _ => return Err(ApiError::RegisterApiFailed(format!("{name} has an unsupported signature")).into()),
+    };
+    M::func_new(linker, mode, name, ty, prefetch, func)
+        .map_err(|e| ApiError::RegisterApiFailed(e.to_string()))?;
     Ok(())
 }
 
+/// Registers the API functions for the kind of storage. Only [`Threaded`]
+/// storage can be used by the async host functions.
+pub(crate) trait Registrar: Storage {
+    /// Registers the API function with the linker in the mode
+    fn func_new(
+        linker: &mut Linker<Context<'_, Self>>,
+        mode: Mode,
+        name: &str,
+        ty: FuncType,
+        prefetch: fn(&[String]) -> Prefetch,
+        func: HostFn<Self>,
+    ) -> Result<(), wasmtime::Error>;
+}
+
+impl Registrar for Local {
+    fn func_new(
+        linker: &mut Linker<Context<'_, Self>>,
+        mode: Mode,
+        name: &str,
+        ty: FuncType,
+        _prefetch: fn(&[String]) -> Prefetch,
+        func: HostFn<Self>,
+    ) -> Result<(), wasmtime::Error>
+    {
+        match mode {
+            Mode::Sync => linker.func_new("wacc", name, ty, func).map(|_| ()),
+            Mode::Async => Err(wasmtime::Error::msg("async execution requires threaded storage")),
+        }
+    }
+}
+
+impl Registrar for Threaded {
+    fn func_new(
+        linker: &mut Linker<Context<'_, Self>>,
+        mode: Mode,
+        name: &str,
+        ty: FuncType,
+        prefetch: fn(&[String]) -> Prefetch,
+        func: HostFn<Self>,
+    ) -> Result<(), wasmtime::Error>
+    {
+        match mode {
+            Mode::Sync => linker.func_new("wacc", name, ty, func),
+            Mode::Async => linker.func_new_async("wacc", name, ty, move |mut caller, params, results| {
+                Box::new(async move {
+                    // find the key-value pairs the function reads, the function
+                    // charges for reading the parameters when it runs
+                    settle(&mut caller)?;
+                    let plan = prefetch(&read_strings(&mut caller, params));
+                    let (current, proposed) = {
+                        let context = caller.data();
+                        (context.current, context.proposed)
+                    };
+
+                    // load them from the async storage
+                    current.prefetch(&plan.current_keys, &plan.current_prefixes).await;
+                    proposed.prefetch(&plan.proposed_keys, &plan.proposed_prefixes).await;
+
+                    func(caller, params, results)
+                })
+            }),
+        }
+        .map(|_| ())
+    }
+}
+
+/// Deducts the cost plus the fuel owed by the context from the store's fuel.
+/// Fails with an out of fuel trap if the budget is exceeded. This does nothing
+/// if the engine doesn't consume fuel.
+pub(crate) fn charge<M: Storage>(caller: &mut Caller<'_, Context<'_, M>>, cost: u64) -> Result<(), wasmtime::Error>
+{
+    let cost = cost.saturating_add(caller.data_mut().meter.take());
+    let fuel = match caller.get_fuel() {
+        Ok(fuel) => fuel,
+        Err(_) => return Ok(()),
+    };
+    if fuel < cost {
+        caller.set_fuel(0)?;
+        return Err(Trap::OutOfFuel.into());
+    }
+    caller.data_mut().meter.set_available(fuel - cost);
+    caller.set_fuel(fuel - cost)
+}
+
+/// Deducts the fuel owed by the context from the store's fuel
+pub(crate) fn settle<M: Storage>(caller: &mut Caller<'_, Context<'_, M>>) -> Result<(), wasmtime::Error>
+{
+    charge(caller, 0)
+}
+
 /// This function takes an offset and length and pulls the associated bytes
-/// from the linear memory and returns it as a string
-pub(crate) fn get_string(
-    caller: &mut Caller<'_, Context<'_>>,
+/// from the linear memory and returns it as a string. Running out of fuel is
+/// the outer error, a trap the WACC function returns, and invalid parameters
+/// are the inner error, which the WACC function reports as a failure.
+pub(crate) fn get_string<M: Storage>(
+    caller: &mut Caller<'_, Context<'_, M>>,
+    params: &[Val],
+) -> Result<Result<String, Error>, wasmtime::Error>
+{
+    // reading the string costs fuel, charge for it before anything is copied
+    // so a huge length runs out of fuel instead of allocating
+    let len = match params.get(1).and_then(Val::i32) {
+        Some(len) => len as u32 as usize,
+        None => return Ok(Err(ApiError::InvalidParam(1).into())),
+    };
+    caller.data_mut().meter.charge_string(len);
+    settle(caller)?;
+
+    Ok(read_string(caller, params))
+}
+
+/// Reads the string parameters, given as pairs of offset and length, without
+/// charging for them. Stops at the first parameter that isn't a valid string.
+pub(crate) fn read_strings<M: Storage>(
+    caller: &mut Caller<'_, Context<'_, M>>,
+    params: &[Val],
+) -> Vec<String>
+{
+    params
+        .chunks_exact(2)
+        .map_while(|p| read_string(caller, p).ok())
+        .collect()
+}
+
+fn read_string<M: Storage>(
+    caller: &mut Caller<'_, Context<'_, M>>,
     params: &[Val],
 ) -> Result<String, Error>
 {
@@ -54,21 +316,21 @@ pub(crate) fn get_string(
         _ => return Err(ApiError::InvalidParam(1).into()),
     };
 
-    // decode the string from the memory
+    // decode the string from the memory, checking the bounds before copying
     let s = {
-        let mut buf = vec![0; len];
-        buf.resize(len, 0u8);
-        mem.read(&caller, ptr, buf.as_mut_slice())
-            .map_err(|_| ApiError::MemoryDecodeError)?;
-        String::from_utf8(buf)?
+        let buf = ptr
+            .checked_add(len)
+            .and_then(|end| mem.data(&caller).get(ptr..end))
+            .ok_or(ApiError::MemoryDecodeError)?;
+        String::from_utf8(buf.to_vec())?
     };
 
     Ok(s)
 }
 
 /// This function takes 
-pub(crate) fn put_string(
-    caller: &mut Caller<'_, Context<'_>>,
+pub(crate) fn put_string<M: Storage>(
+    caller: &mut Caller<'_, Context<'_, M>>,
     s: &str,
     results: &mut [Val],
 ) -> Result<(), Error>
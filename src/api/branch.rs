@@ -1,27 +1,35 @@
 // SPDX-License-Identifier: FSL-1.1
 use crate::{
-    api,
-    error::ApiError,
+    api::{self, Mode, Registrar},
+    storage::Storage,
     Context, Error,
 };
-use wasmtime::{AsContextMut, Caller, Engine, FuncType, Linker, Val, ValType::*};
+use wasmtime::{AsContextMut, Caller, Engine, Linker, Val};
 
-pub(crate) fn add_to_linker(engine: &Engine, linker: &mut Linker<Context<'_>>) -> Result<(), Error>
+pub(crate) fn add_to_linker<M: Registrar>(engine: &Engine, linker: &mut Linker<Context<'_, M>>, mode: Mode) -> Result<(), Error>
 {
-    linker
-        .func_new("wacc", "_branch", FuncType::new(engine, [I32, I32], [I32, I32]), branch)
-        .map_err(|e| ApiError::RegisterApiFailed(e.to_string()))?;
-    Ok(())
+    api::func_new(
+        engine,
+        linker,
+        mode,
+        "_branch",
+        api::no_prefetch,
+        branch,
+    )
 }
 
-pub(crate) fn branch(
-    mut caller: Caller<'_, Context<'_>>,
+pub(crate) fn branch<M: Storage>(
+    mut caller: Caller<'_, Context<'_, M>>,
     params: &[Val],
     results: &mut [Val],
 ) -> Result<(), wasmtime::Error>
 {
+    // charge for the call
+    let cost = caller.data().meter.schedule.branch;
+    api::charge(&mut caller, cost)?;
+
     // get the string parameter
-    let ret = api::get_string(&mut caller, params);
+    let ret = api::get_string(&mut caller, params)?;
 
     let key = {
         // get the context
@@ -30,7 +38,7 @@ pub(crate) fn branch(
 
         // get the full key given the context
         match ret {
-            Ok(key) => context.branch(&key),
+            Ok(key) => context.expand_branch(&key),
             Err(e) => {
                 context.fail(&e.to_string());
                 return Ok(());
@@ -45,5 +53,8 @@ pub(crate) fn branch(
         context.fail(&e.to_string());
     }
 
+    // charge for the data processed
+    api::settle(&mut caller)?;
+
     Ok(())
 }
@@ -0,0 +1,84 @@
+// SPDX-License-Identifier: FSL-1.1
+use crate::{
+    api::{self, Mode, Prefetch, Registrar},
+    storage::Storage,
+    Context, Error,
+};
+use log::info;
+use wasmtime::{AsContextMut, Caller, Engine, Linker, Val};
+
+pub(crate) fn add_to_linker<M: Registrar>(engine: &Engine, linker: &mut Linker<Context<'_, M>>, mode: Mode) -> Result<(), Error>
+{
+    api::func_new(
+        engine,
+        linker,
+        mode,
+        "_check_aggregate_signature",
+        prefetch,
+        check_aggregate_signature,
+    )
+}
+
+/// The key-value pairs read, the public keys and the shared or per-signer messages
+pub(crate) fn prefetch(args: &[String]) -> Prefetch {
+    Prefetch {
+        current_prefixes: api::arg(args, 0),
+        proposed_prefixes: api::arg(args, 1),
+        ..Default::default()
+    }
+}
+
+pub(crate) fn check_aggregate_signature<M: Storage>(
+    mut caller: Caller<'_, Context<'_, M>>,
+    params: &[Val],
+    results: &mut [Val],
+) -> Result<(), wasmtime::Error>
+{
+    // charge for the call
+    let cost = caller.data().meter.schedule.check_aggregate_signature;
+    api::charge(&mut caller, cost)?;
+
+    // check preconditions
+    if params.len() != 4 {
+        let mut ctx = caller.as_context_mut();
+        let context = ctx.data_mut();
+        results[0] = context.fail("check_aggregate_signature requires two string parameters");
+        return Ok(())
+    }
+
+    // get the index and length of the pubkey prefix and message key-path strings
+    let (k, m) = params.split_at(2);
+    info!("check_aggregate_signature: {k:?}, {m:?}");
+
+    // get the key-path prefix for the public keys
+    let prefix = match api::get_string(&mut caller, k)? {
+        Ok(kp) => kp,
+        Err(e) => {
+            let mut ctx = caller.as_context_mut();
+            let context = ctx.data_mut();
+            results[0] = context.fail(&e.to_string());
+            return Ok(());
+        }
+    };
+
+    // get the key-path string for the message
+    let msg = match api::get_string(&mut caller, m)? {
+        Ok(msg) => msg,
+        Err(e) => {
+            let mut ctx = caller.as_context_mut();
+            let context = ctx.data_mut();
+            results[0] = context.fail(&e.to_string());
+            return Ok(());
+        }
+    };
+
+    // check the aggregated signature over the messages
+    let mut ctx = caller.as_context_mut();
+    let context = ctx.data_mut();
+    results[0] = context.check_aggregate_signature(&prefix, &msg);
+
+    // charge for the data processed
+    api::settle(&mut caller)?;
+
+    Ok(())
+}
@@ -0,0 +1,84 @@
+// SPDX-License-Identifier: FSL-1.1
+use crate::{
+    api::{self, Mode, Prefetch, Registrar},
+    storage::Storage,
+    Context, Error,
+};
+use log::info;
+use wasmtime::{AsContextMut, Caller, Engine, Linker, Val};
+
+pub(crate) fn add_to_linker<M: Registrar>(engine: &Engine, linker: &mut Linker<Context<'_, M>>, mode: Mode) -> Result<(), Error>
+{
+    api::func_new(
+        engine,
+        linker,
+        mode,
+        "_check_fingerprint",
+        prefetch,
+        check_fingerprint,
+    )
+}
+
+/// The key-value pairs read, the fingerprint and the proposed public key
+pub(crate) fn prefetch(args: &[String]) -> Prefetch {
+    Prefetch {
+        current_keys: api::arg(args, 1),
+        proposed_keys: api::arg(args, 0),
+        ..Default::default()
+    }
+}
+
+pub(crate) fn check_fingerprint<M: Storage>(
+    mut caller: Caller<'_, Context<'_, M>>,
+    params: &[Val],
+    results: &mut [Val],
+) -> Result<(), wasmtime::Error>
+{
+    // charge for the call
+    let cost = caller.data().meter.schedule.check_fingerprint;
+    api::charge(&mut caller, cost)?;
+
+    // check preconditions
+    if params.len() != 4 {
+        let mut ctx = caller.as_context_mut();
+        let context = ctx.data_mut();
+        results[0] = context.fail("check_fingerprint requires two string parameters");
+        return Ok(())
+    }
+
+    // get the index and length of the pubkey and fingerprint key-path strings
+    let (k, m) = params.split_at(2);
+    info!("check_fingerprint: {k:?}, {m:?}");
+
+    // get the key-path string for the public key
+    let key = match api::get_string(&mut caller, k)? {
+        Ok(kp) => kp,
+        Err(e) => {
+            let mut ctx = caller.as_context_mut();
+            let context = ctx.data_mut();
+            results[0] = context.fail(&e.to_string());
+            return Ok(());
+        }
+    };
+
+    // get the key-path string for the fingerprint
+    let fingerprint = match api::get_string(&mut caller, m)? {
+        Ok(fp) => fp,
+        Err(e) => {
+            let mut ctx = caller.as_context_mut();
+            let context = ctx.data_mut();
+            results[0] = context.fail(&e.to_string());
+            return Ok(());
+        }
+    };
+
+    // check the fingerprint of the public key
+    let mut ctx = caller.as_context_mut();
+    let context = ctx.data_mut();
+    results[0] = context.check_fingerprint(&key, &fingerprint);
+
+    // charge for the data processed
+    api::settle(&mut caller)?;
+
+    Ok(())
+}
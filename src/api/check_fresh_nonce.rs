@@ -0,0 +1,84 @@
+// SPDX-License-Identifier: FSL-1.1
+use crate::{
+    api::{self, Mode, Prefetch, Registrar},
+    storage::Storage,
+    Context, Error,
+};
+use log::info;
+use wasmtime::{AsContextMut, Caller, Engine, Linker, Val};
+
+pub(crate) fn add_to_linker<M: Registrar>(engine: &Engine, linker: &mut Linker<Context<'_, M>>, mode: Mode) -> Result<(), Error>
+{
+    api::func_new(
+        engine,
+        linker,
+        mode,
+        "_check_fresh_nonce",
+        prefetch,
+        check_fresh_nonce,
+    )
+}
+
+/// The key-value pairs read, the proposed nonce and the used nonces
+pub(crate) fn prefetch(args: &[String]) -> Prefetch {
+    Prefetch {
+        current_prefixes: api::arg(args, 1),
+        proposed_keys: api::arg(args, 0),
+        ..Default::default()
+    }
+}
+
+pub(crate) fn check_fresh_nonce<M: Storage>(
+    mut caller: Caller<'_, Context<'_, M>>,
+    params: &[Val],
+    results: &mut [Val],
+) -> Result<(), wasmtime::Error>
+{
+    // charge for the call
+    let cost = caller.data().meter.schedule.check_fresh_nonce;
+    api::charge(&mut caller, cost)?;
+
+    // check preconditions
+    if params.len() != 4 {
+        let mut ctx = caller.as_context_mut();
+        let context = ctx.data_mut();
+        results[0] = context.fail("check_fresh_nonce requires two string parameters");
+        return Ok(())
+    }
+
+    // get the index and length of the nonce key-path and used-nonce prefix strings
+    let (k, m) = params.split_at(2);
+    info!("check_fresh_nonce: {k:?}, {m:?}");
+
+    // get the key-path string for the nonce
+    let key = match api::get_string(&mut caller, k)? {
+        Ok(kp) => kp,
+        Err(e) => {
+            let mut ctx = caller.as_context_mut();
+            let context = ctx.data_mut();
+            results[0] = context.fail(&e.to_string());
+            return Ok(());
+        }
+    };
+
+    // get the key-path prefix for the used nonces
+    let prefix = match api::get_string(&mut caller, m)? {
+        Ok(prefix) => prefix,
+        Err(e) => {
+            let mut ctx = caller.as_context_mut();
+            let context = ctx.data_mut();
+            results[0] = context.fail(&e.to_string());
+            return Ok(());
+        }
+    };
+
+    // check that the nonce hasn't been used
+    let mut ctx = caller.as_context_mut();
+    let context = ctx.data_mut();
+    results[0] = context.check_fresh_nonce(&key, &prefix);
+
+    // charge for the data processed
+    api::settle(&mut caller)?;
+
+    Ok(())
+}
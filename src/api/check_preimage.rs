@@ -1,27 +1,43 @@
 // SPDX-License-Identifier: FSL-1.1
 use crate::{
-    api,
-    error::ApiError,
+    api::{self, Mode, Prefetch, Registrar},
+    storage::Storage,
     Context, Error,
 };
-use wasmtime::{AsContextMut, Caller, Engine, FuncType, Linker, Val, ValType::*};
+use wasmtime::{AsContextMut, Caller, Engine, Linker, Val};
 
-pub(crate) fn add_to_linker(engine: &Engine, linker: &mut Linker<Context<'_>>) -> Result<(), Error>
+pub(crate) fn add_to_linker<M: Registrar>(engine: &Engine, linker: &mut Linker<Context<'_, M>>, mode: Mode) -> Result<(), Error>
 {
-    linker
-        .func_new("wacc", "_check_preimage", FuncType::new(engine, [I32, I32], [I32]), check_preimage)
-        .map_err(|e| ApiError::RegisterApiFailed(e.to_string()))?;
-    Ok(())
+    api::func_new(
+        engine,
+        linker,
+        mode,
+        "_check_preimage",
+        prefetch,
+        check_preimage,
+    )
+}
+
+/// The key-value pairs read, the hash checked by check_preimage
+pub(crate) fn prefetch(args: &[String]) -> Prefetch {
+    Prefetch {
+        current_keys: api::arg(args, 0),
+        ..Default::default()
+    }
 }
 
-pub(crate) fn check_preimage(
-    mut caller: Caller<'_, Context<'_>>,
+pub(crate) fn check_preimage<M: Storage>(
+    mut caller: Caller<'_, Context<'_, M>>,
     params: &[Val],
     results: &mut [Val],
 ) -> Result<(), wasmtime::Error>
 {
+    // charge for the call
+    let cost = caller.data().meter.schedule.check_preimage;
+    api::charge(&mut caller, cost)?;
+
     // get the string parameter
-    let ret = api::get_string(&mut caller, params);
+    let ret = api::get_string(&mut caller, params)?;
 
     // get the context
     let mut ctx = caller.as_context_mut();
@@ -33,5 +49,8 @@ pub(crate) fn check_preimage(
         Err(e) => context.fail(&e.to_string()),
     };
 
+    // charge for the data processed
+    api::settle(&mut caller)?;
+
     Ok(())
 }
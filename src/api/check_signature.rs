@@ -1,31 +1,63 @@
 // SPDX-License-Identifier: FSL-1.1
 use crate::{
-    api,
+    api::{self, Mode, Prefetch, Registrar},
     error::ApiError,
+    storage::Storage,
+    vm::SignedMessage,
     Context, Error,
 };
 use log::info;
-use wasmtime::{AsContextMut, Caller, Engine, FuncType, Linker, Val, ValType::*};
+use multicodec::Codec;
+use wasmtime::{AsContextMut, Caller, Engine, Linker, Val};
 
-pub(crate) fn add_to_linker(engine: &Engine, linker: &mut Linker<Context<'_>>) -> Result<(), Error>
+pub(crate) fn add_to_linker<M: Registrar>(engine: &Engine, linker: &mut Linker<Context<'_, M>>, mode: Mode) -> Result<(), Error>
 {
-    linker
-        .func_new(
-            "wacc",
-            "_check_signature",
-            FuncType::new(engine, [I32, I32, I32, I32], [I32]),
-            check_signature,
-        )
-        .map_err(|e| ApiError::RegisterApiFailed(e.to_string()))?;
+    api::func_new(
+        engine,
+        linker,
+        mode,
+        "_check_signature",
+        prefetch,
+        check_signature,
+    )?;
+    api::func_new(
+        engine,
+        linker,
+        mode,
+        "_check_signature_hashed",
+        prefetch,
+        check_signature_hashed,
+    )?;
+    api::func_new(
+        engine,
+        linker,
+        mode,
+        "_check_signature_domain",
+        prefetch,
+        check_signature_domain,
+    )?;
     Ok(())
 }
 
-pub(crate) fn check_signature(
-    mut caller: Caller<'_, Context<'_>>,
+/// The key-value pairs read, the public key and the message
+pub(crate) fn prefetch(args: &[String]) -> Prefetch {
+    Prefetch {
+        current_keys: api::arg(args, 0),
+        proposed_keys: api::arg(args, 1),
+        ..Default::default()
+    }
+}
+
+pub(crate) fn check_signature<M: Storage>(
+    mut caller: Caller<'_, Context<'_, M>>,
     params: &[Val],
     results: &mut [Val],
 ) -> Result<(), wasmtime::Error>
 {
+    // charge for the call
+    let cost = caller.data().meter.schedule.check_signature;
+    api::charge(&mut caller, cost)?;
+
     // check preconditions
     if params.len() != 4 {
         let mut ctx = caller.as_context_mut();
@@ -34,36 +66,117 @@ pub(crate) fn check_signature(
         return Ok(())
     }
 
+    results[0] = check_signature_with(&mut caller, params, &SignedMessage::Raw)?;
+
+    // charge for the data processed
+    api::settle(&mut caller)?;
+
+    Ok(())
+}
+
+pub(crate) fn check_signature_hashed<M: Storage>(
+    mut caller: Caller<'_, Context<'_, M>>,
+    params: &[Val],
+    results: &mut [Val],
+) -> Result<(), wasmtime::Error>
+{
+    // charge for the call
+    let cost = caller.data().meter.schedule.check_signature;
+    api::charge(&mut caller, cost)?;
+
+    // check preconditions
+    if params.len() != 5 {
+        let mut ctx = caller.as_context_mut();
+        let context = ctx.data_mut();
+        results[0] = context.fail("check_signature_hashed requires two string parameters and a hash codec");
+        return Ok(())
+    }
+
+    // get the hash codec used to construct the message
+    let codec = match params[4].i32() {
+        Some(code) => match Codec::try_from(code as u32 as u64) {
+            Ok(codec) => codec,
+            Err(e) => {
+                let mut ctx = caller.as_context_mut();
+                let context = ctx.data_mut();
+                results[0] = context.fail(&e.to_string());
+                return Ok(());
+            }
+        },
+        None => {
+            let mut ctx = caller.as_context_mut();
+            let context = ctx.data_mut();
+            results[0] = context.fail(&ApiError::InvalidParam(4).to_string());
+            return Ok(());
+        }
+    };
+
+    results[0] = check_signature_with(&mut caller, &params[..4], &SignedMessage::Hashed(codec))?;
+
+    // charge for the data processed
+    api::settle(&mut caller)?;
+
+    Ok(())
+}
+
+pub(crate) fn check_signature_domain<M: Storage>(
+    mut caller: Caller<'_, Context<'_, M>>,
+    params: &[Val],
+    results: &mut [Val],
+) -> Result<(), wasmtime::Error>
+{
+    // charge for the call
+    let cost = caller.data().meter.schedule.check_signature;
+    api::charge(&mut caller, cost)?;
+
+    // check preconditions
+    if params.len() != 4 {
+        let mut ctx = caller.as_context_mut();
+        let context = ctx.data_mut();
+        results[0] = context.fail("check_signature_domain requires two string parameters");
+        return Ok(())
+    }
+
+    results[0] = check_signature_with(&mut caller, params, &SignedMessage::Domain)?;
+
+    // charge for the data processed
+    api::settle(&mut caller)?;
+
+    Ok(())
+}
+
+fn check_signature_with<M: Storage>(
+    caller: &mut Caller<'_, Context<'_, M>>,
+    params: &[Val],
+    format: &SignedMessage,
+) -> Result<Val, wasmtime::Error>
+{
     // get the index and length of the pubkey and message key-path strings
     let (k, m) = params.split_at(2);
     info!("check_signature: {k:?}, {m:?}");
 
     // get the key-path string for the public key
-    let key = match api::get_string(&mut caller, k) {
+    let key = match api::get_string(caller, k)? {
         Ok(kp) => kp,
         Err(e) => {
             let mut ctx = caller.as_context_mut();
             let context = ctx.data_mut();
-            results[0] = context.fail(&e.to_string());
-            return Ok(());
+            return Ok(context.fail(&e.to_string()));
         }
     };
 
     // get the key-path string for the message
-    let msg = match api::get_string(&mut caller, m) {
+    let msg = match api::get_string(caller, m)? {
         Ok(msg) => msg,
         Err(e) => {
             let mut ctx = caller.as_context_mut();
             let context = ctx.data_mut();
-            results[0] = context.fail(&e.to_string());
-            return Ok(());
+            return Ok(context.fail(&e.to_string()));
         }
     };
 
     // check the digital signature over the message
     let mut ctx = caller.as_context_mut();
     let context = ctx.data_mut();
-    results[0] = context.check_signature(&key, &msg);
-
-    Ok(())
+    Ok(context.check_signature_with(&key, &msg, format))
 }
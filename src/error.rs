@@ -66,7 +66,73 @@ pub enum VmError {
     /// Missing VM context
     #[error("Missing VM context")]
     MissingContext,
+    /// Missing parameter or return stack
+    #[error("Missing VM stack")]
+    MissingStack,
     /// Invalid key-path for the key-value store
     #[error("Invalid key-path {0}")]
     InvalidKeyPath(String),
+    /// The signature policy rejected the key or signature algorithm
+    #[error("signature policy violation: {0}")]
+    SignaturePolicy(String),
+    /// The runtime is not configured to support the builder options
+    #[error("Incompatible runtime: {0}")]
+    IncompatibleRuntime(String),
+    /// The instance was built with async support and must be run async
+    #[error("The instance has async support and must be run with run_async")]
+    AsyncRequired,
+    /// The instance was built without async support and can't be run async
+    #[error("The instance doesn't have async support")]
+    AsyncUnsupported,
+    /// The precompiled artifact is malformed
+    #[error("Invalid artifact: {0}")]
+    InvalidArtifact(String),
+    /// The precompiled artifact was compiled with a different engine configuration
+    #[error("Incompatible artifact: {0}")]
+    IncompatibleArtifact(String),
+    /// A cached artifact is malformed or fails authentication
+    #[error("Corrupt cache entry {0}")]
+    CorruptCache(String),
+    /// The script uses features the deterministic profile forbids
+    #[error("Non-deterministic script: {0}")]
+    NonDeterministic(String),
+    /// The script ran out of fuel
+    #[error("Out of fuel after consuming the budget of {0}")]
+    OutOfFuel(u64),
+    /// The script ran past its deadline
+    #[error("Deadline of {0:?} exceeded")]
+    Timeout(std::time::Duration),
+    /// The execution was aborted with an interrupt handle
+    #[error("Execution cancelled")]
+    Cancelled,
+    /// The script exceeds a static resource limit
+    #[error("{limit} of {value} exceeds the limit of {max}")]
+    LimitExceeded {
+        /// The name of the limit
+        limit: String,
+        /// The value declared by the script
+        value: u64,
+        /// The limit
+        max: u64,
+    },
+    /// The script imports a function that isn't available in its phase
+    #[error("{name} is not available in the {phase} phase")]
+    UnavailableImport {
+        /// The execution phase
+        phase: crate::vm::Phase,
+        /// The name of the imported function
+        name: String,
+    },
+    /// The script in the text format failed to parse
+    #[error("Invalid text script: {0}")]
+    InvalidText(String),
+    /// The script is in the text format but text support is not enabled
+    #[error("Text scripts are not supported without the wat feature")]
+    TextUnsupported,
+    /// The script was expected to be in the binary format
+    #[error("The script is not in the wasm binary format")]
+    NotBinary,
+    /// The pre-flight check found keys the scripts read missing from the state
+    #[error("Missing keys: {}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "))]
+    MissingKeys(Vec<crate::vm::MissingKey>),
 }
@@ -1,4 +1,16 @@
 // SPDX-License-Identifier: FSL-1.1
+use std::{future::Future, pin::Pin};
+
+/// A boxed future returned by the async storage traits
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// The interface to an async key-value pairs store
+pub mod async_pairs;
+pub use async_pairs::{AsyncPairs, PrefetchPairs};
+
+/// The interface to an async value stack
+pub mod async_stack;
+pub use async_stack::{AsyncStack, StackBuffer};
 
 /// The interface to a key-value pairs store
 pub mod pairs;
@@ -7,3 +19,7 @@ pub use pairs::Pairs;
 /// The interface to a value stack
 pub mod stack;
 pub use stack::Stack;
+
+/// The storage that can be used by sync and async executions
+pub mod threaded;
+pub use threaded::{Local, Storage, Threaded, ThreadedPairs, ThreadedStack};
@@ -0,0 +1,100 @@
+// SPDX-License-Identifier: FSL-1.1
+use crate::{
+    storage::{BoxFuture, Pairs},
+    Value,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Mutex,
+};
+
+/// Trait to an async key-value storage mechanism
+pub trait AsyncPairs: Send + Sync {
+    /// get a value associated with the key
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<Value>>;
+
+    /// add a key-value pair to the storage, returns the previous value if the
+    /// key already exists in the data structure
+    fn put<'a>(&'a mut self, key: &'a str, value: &'a Value) -> BoxFuture<'a, Option<Value>>;
+
+    /// get the keys in the storage that start with the given prefix, in order
+    fn keys<'a>(&'a self, prefix: &'a str) -> BoxFuture<'a, Vec<String>>;
+}
+
+/// Adapts [`AsyncPairs`] to [`Pairs`] for async executions. The values read
+/// by each WACC function are loaded from the async source before the function
+/// runs. Writes are kept in memory and are not written to the source.
+pub struct PrefetchPairs<'a> {
+    source: &'a dyn AsyncPairs,
+    values: Mutex<BTreeMap<String, Option<Value>>>,
+    prefixes: Mutex<BTreeSet<String>>,
+}
+
+impl<'a> PrefetchPairs<'a> {
+    /// Create a new adapter for the async source
+    pub fn new(source: &'a dyn AsyncPairs) -> Self {
+        Self {
+            source,
+            values: Mutex::new(BTreeMap::default()),
+            prefixes: Mutex::new(BTreeSet::default()),
+        }
+    }
+
+    fn is_loaded(&self, key: &str) -> bool {
+        self.values.lock().map(|v| v.contains_key(key)).unwrap_or_default()
+    }
+
+    fn insert(&self, key: &str, value: Option<Value>) {
+        if let Ok(mut values) = self.values.lock() {
+            values.entry(key.to_string()).or_insert(value);
+        }
+    }
+
+    async fn load(&self, key: &str) {
+        if !self.is_loaded(key) {
+            let value = self.source.get(key).await;
+            self.insert(key, value);
+        }
+    }
+}
+
+impl Pairs for PrefetchPairs<'_> {
+    fn get(&self, key: &str) -> Option<Value> {
+        self.values.lock().ok()?.get(key).cloned().flatten()
+    }
+
+    fn put(&mut self, key: &str, value: &Value) -> Option<Value> {
+        self.values.get_mut().ok()?.insert(key.to_string(), Some(value.clone())).flatten()
+    }
+
+    fn keys(&self, prefix: &str) -> Vec<String> {
+        match self.values.lock() {
+            Ok(values) => values
+                .iter()
+                .filter(|(k, v)| k.starts_with(prefix) && v.is_some())
+                .map(|(k, _)| k.clone())
+                .collect(),
+            Err(_) => Vec::default(),
+        }
+    }
+
+    fn prefetch<'a>(&'a self, keys: &'a [String], prefixes: &'a [String]) -> BoxFuture<'a, ()> {
+        Box::pin(async move {
+            for key in keys {
+                self.load(key).await;
+            }
+            for prefix in prefixes {
+                let loaded = self.prefixes.lock().map(|p| p.contains(prefix)).unwrap_or_default();
+                if loaded {
+                    continue;
+                }
+                for key in self.source.keys(prefix).await {
+                    self.load(&key).await;
+                }
+                if let Ok(mut p) = self.prefixes.lock() {
+                    p.insert(prefix.clone());
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,84 @@
+// SPDX-License-Identifier: FSL-1.1
+use crate::{
+    storage::{BoxFuture, Stack},
+    Value,
+};
+
+/// Trait for an async value stack
+pub trait AsyncStack: Send + Sync {
+    /// push a value onto the stack
+    fn push(&mut self, value: Value) -> BoxFuture<'_, ()>;
+
+    /// remove the last top value from the stack
+    fn pop(&mut self) -> BoxFuture<'_, Option<Value>>;
+
+    /// get a reference to the top value on the stack
+    fn top(&self) -> BoxFuture<'_, Option<Value>>;
+
+    /// peek at the item at the given index
+    fn peek(&self, idx: usize) -> BoxFuture<'_, Option<Value>>;
+
+    /// return the number of values on the stack
+    fn len(&self) -> BoxFuture<'_, usize>;
+
+    /// return if the stack is empty
+    fn is_empty(&self) -> BoxFuture<'_, bool>;
+}
+
+/// An in-memory [`Stack`] that is loaded from an [`AsyncStack`] before an
+/// async execution and stored back to it afterwards
+#[derive(Clone, Debug, Default)]
+pub struct StackBuffer {
+    values: Vec<Value>,
+}
+
+impl StackBuffer {
+    /// Load the values from the async stack
+    pub async fn load(source: &dyn AsyncStack) -> Self {
+        let len = source.len().await;
+        let mut values = Vec::with_capacity(len);
+        for idx in (0..len).rev() {
+            if let Some(value) = source.peek(idx).await {
+                values.push(value);
+            }
+        }
+        Self { values }
+    }
+
+    /// Replace the values of the async stack with the values in the buffer
+    pub async fn store(&self, dest: &mut dyn AsyncStack) {
+        while dest.pop().await.is_some() {}
+        for value in &self.values {
+            dest.push(value.clone()).await;
+        }
+    }
+}
+
+impl Stack for StackBuffer {
+    fn push(&mut self, value: Value) {
+        self.values.push(value);
+    }
+
+    fn pop(&mut self) -> Option<Value> {
+        self.values.pop()
+    }
+
+    fn top(&self) -> Option<Value> {
+        self.values.last().cloned()
+    }
+
+    fn peek(&self, idx: usize) -> Option<Value> {
+        if idx >= self.values.len() {
+            return None;
+        }
+        Some(self.values[self.values.len() - 1 - idx].clone())
+    }
+
+    fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
@@ -1,5 +1,5 @@
 // SPDX-License-Identifier: FSL-1.1
-use crate::Value;
+use crate::{storage::BoxFuture, Value};
 
 /// Trait to a key-value storage mechanism
 pub trait Pairs {
@@ -9,4 +9,18 @@ pub trait Pairs {
     /// add a key-value pair to the storage, returns the previous value if the
     /// key already exists in the data structure
     fn put(&mut self, key: &str, value: &Value) -> Option<Value>;
+
+    /// get the keys in the storage that start with the given prefix, in order.
+    /// storage that cannot list its keys returns none
+    fn keys(&self, _prefix: &str) -> Vec<String> {
+        Vec::default()
+    }
+
+    /// load the values associated with the keys, and with all of the keys that
+    /// start with the prefixes, before they are read. this is called by async
+    /// executions and does nothing unless the storage is backed by an async
+    /// source, see [`crate::storage::PrefetchPairs`]
+    fn prefetch<'a>(&'a self, _keys: &'a [String], _prefixes: &'a [String]) -> BoxFuture<'a, ()> {
+        Box::pin(std::future::ready(()))
+    }
 }
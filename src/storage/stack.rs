@@ -21,3 +21,32 @@ pub trait Stack {
     /// return if the stack is empty
     fn is_empty(&self) -> bool;
 }
+
+impl Stack for Vec<Value> {
+    fn push(&mut self, value: Value) {
+        Vec::push(self, value);
+    }
+
+    fn pop(&mut self) -> Option<Value> {
+        Vec::pop(self)
+    }
+
+    fn top(&self) -> Option<Value> {
+        self.last().cloned()
+    }
+
+    fn peek(&self, idx: usize) -> Option<Value> {
+        if idx >= Vec::len(self) {
+            return None;
+        }
+        Some(self[Vec::len(self) - 1 - idx].clone())
+    }
+
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        Vec::is_empty(self)
+    }
+}
@@ -0,0 +1,41 @@
+// SPDX-License-Identifier: FSL-1.1
+use crate::storage::{Pairs, Stack};
+
+/// A [`Pairs`] that can be shared with other threads, required for async
+/// executions
+pub trait ThreadedPairs: Pairs + Send + Sync {}
+
+impl<T: Pairs + Send + Sync + ?Sized> ThreadedPairs for T {}
+
+/// A [`Stack`] that can be sent to other threads, required for async
+/// executions
+pub trait ThreadedStack: Stack + Send {}
+
+impl<T: Stack + Send + ?Sized> ThreadedStack for T {}
+
+/// The kind of storage a [`crate::vm::Context`] refers to
+pub trait Storage: Sized + 'static {
+    /// The key-value pairs of the current and proposed states
+    type Pairs<'a>: Pairs + ?Sized + 'a;
+
+    /// The parameter and return stacks
+    type Stack<'a>: Stack + ?Sized + 'a;
+}
+
+/// Storage that stays on the calling thread, for sync executions
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Local;
+
+impl Storage for Local {
+    type Pairs<'a> = dyn Pairs + 'a;
+    type Stack<'a> = dyn Stack + 'a;
+}
+
+/// Storage that can be used from other threads, for async executions
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Threaded;
+
+impl Storage for Threaded {
+    type Pairs<'a> = dyn ThreadedPairs + 'a;
+    type Stack<'a> = dyn ThreadedStack + 'a;
+}
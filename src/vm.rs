@@ -1,22 +1,83 @@
 // SPDX-License-Identifier: FSL-1.1
 
+/// aggregate signature verification
+pub(crate) mod aggregate;
+
+/// static key-path analysis of scripts
+pub mod analysis;
+
+/// precompiled artifact format
+pub mod artifact;
+
 /// virtual machine builder
 pub mod builder;
 
+/// on-disk compiled module cache
+pub mod cache;
+
 /// wasm code compiler
 pub mod compiler;
 
 /// virtual machine execution context
 pub mod context;
 
+/// host function fuel costs
+pub mod cost;
+
 /// virtual machine instance
 pub mod instance;
 
+/// static script resource limits
+pub mod limits;
+
+/// precedence ordered lock script sets
+pub mod lockset;
+
+/// signed message construction
+pub mod message;
+
+/// structured result of an execution
+pub mod outcome;
+
+/// script execution phases
+pub mod phase;
+
+/// signature algorithm policy
+pub mod policy;
+
+/// engine execution profiles
+pub mod profile;
+
+/// shared engine and compiled module cache
+pub mod runtime;
+
+/// static validation of scripts
+pub mod validator;
+
+/// wasm text format support
+pub(crate) mod text;
+
 /// value wrapper used in the virtual machine
 pub mod value;
 
+/// unlock and lock script pair verification
+pub mod verifier;
+
+pub use analysis::{analyze, Analysis, KeyRef, Read, State};
 pub use builder::Builder;
+pub use cache::DiskCache;
 pub use compiler::Compiler;
-pub use context::Context;
-pub use instance::Instance;
+pub use context::{AccessSet, Check, Context, ContextBuilder};
+pub use cost::{CostSchedule, Meter};
+pub use instance::{Fuel, Instance, InterruptHandle, RunResult};
+pub use limits::Limits;
+pub use lockset::{Lock, LockOutcome, LockSetVerification, Satisfied};
+pub use message::SignedMessage;
+pub use outcome::RunOutcome;
+pub use phase::Phase;
+pub use policy::SignaturePolicy;
+pub use profile::Profile;
+pub use runtime::{EngineOptions, Runtime, RuntimeBuilder};
+pub use validator::{Diagnostic, Validator};
 pub use value::Value;
+pub use verifier::{MissingKey, Verification, Verifier};
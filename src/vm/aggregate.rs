@@ -0,0 +1,71 @@
+// SPDX-License-Identifier: FSL-1.1
+use crate::Error;
+use blsful::{AggregateSignature, Bls12381G1Impl, Bls12381G2Impl, BlsSignatureImpl, PublicKey};
+use multicodec::Codec;
+use multikey::{Multikey, Views as _};
+use multisig::{Multisig, Views as _};
+use multiutil::CodecInfo;
+
+/// Verifies an aggregated BLS12-381 signature against the public keys and
+/// the message signed by each of them. The signature data is an encoded
+/// blsful AggregateSignature in the same group as the public keys. Only the
+/// basic and message augmentation schemes are accepted; the proof of
+/// possession scheme is only safe when every public key has a verified proof
+/// of possession, which the committed keys do not carry.
+pub(crate) fn verify_aggregate(
+    pubkeys: &[Multikey],
+    sig: &Multisig,
+    msgs: &[Vec<u8>],
+) -> Result<(), Error>
+{
+    if pubkeys.is_empty() || pubkeys.len() != msgs.len() {
+        return Err(Error::custom(&format!(
+            "aggregate signature needs a message for each of the {} public keys, found {}",
+            pubkeys.len(),
+            msgs.len()
+        )));
+    }
+
+    match sig.codec() {
+        Codec::Bls12381G1Msig => verify::<Bls12381G1Impl>(Codec::Bls12381G1Pub, pubkeys, sig, msgs),
+        Codec::Bls12381G2Msig => verify::<Bls12381G2Impl>(Codec::Bls12381G2Pub, pubkeys, sig, msgs),
+        codec => Err(Error::custom(&format!("{codec:?} is not an aggregatable signature"))),
+    }
+}
+
+fn verify<C: BlsSignatureImpl>(
+    key_codec: Codec,
+    pubkeys: &[Multikey],
+    sig: &Multisig,
+    msgs: &[Vec<u8>],
+) -> Result<(), Error>
+{
+    // decode the aggregate signature
+    let sig_bytes = sig
+        .data_view()
+        .map_err(|e| Error::custom(&e))?
+        .sig_bytes()
+        .map_err(|e| Error::custom(&e))?;
+    let agg = AggregateSignature::<C>::try_from(sig_bytes.as_slice()).map_err(|e| Error::custom(&e))?;
+    if matches!(agg, AggregateSignature::ProofOfPossession(_)) {
+        return Err(Error::custom("proof of possession aggregate signatures are not supported"));
+    }
+
+    // decode the public keys and pair them up with their messages
+    let mut data = Vec::with_capacity(pubkeys.len());
+    for (mk, msg) in pubkeys.iter().zip(msgs) {
+        if mk.codec() != key_codec {
+            return Err(Error::custom(&format!("{:?} key cannot verify a {:?} signature", mk.codec(), sig.codec())));
+        }
+        let key_bytes = mk
+            .data_view()
+            .map_err(|e| Error::custom(&e))?
+            .key_bytes()
+            .map_err(|e| Error::custom(&e))?;
+        let pk = PublicKey::<C>::try_from(key_bytes.as_slice()).map_err(|e| Error::custom(&e))?;
+        data.push((pk, msg.as_slice()));
+    }
+
+    // verify the aggregate
+    agg.verify(data.as_slice()).map_err(|e| Error::custom(&e))
+}
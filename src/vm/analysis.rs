@@ -0,0 +1,250 @@
+// SPDX-License-Identifier: FSL-1.1
+use crate::{
+    api,
+    vm::{text, validator::WACC_MODULE},
+    Error,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+use wasmparser::{DataKind, Operator, Parser, Payload, TypeRef};
+
+/// A key-path passed to a WACC function
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct KeyRef {
+    /// The name of the WACC function
+    pub function: String,
+    /// The index of the string parameter
+    pub arg: usize,
+    /// The key-path
+    pub key: String,
+    /// True if the key-path is relative to the branch context, that is, it
+    /// was passed through _branch first
+    pub branched: bool,
+}
+
+/// The state a key is read from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum State {
+    /// The current state
+    Current,
+    /// The proposed state update
+    Proposed,
+}
+
+impl fmt::Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            State::Current => write!(f, "current"),
+            State::Proposed => write!(f, "proposed"),
+        }
+    }
+}
+
+/// A key a WACC function reads
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Read {
+    /// The state the key is read from
+    pub state: State,
+    /// The full key-path
+    pub key: String,
+    /// The name of the WACC function
+    pub function: String,
+}
+
+/// The WACC functions and key-paths a script references
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Analysis {
+    /// The WACC functions the script imports
+    pub functions: BTreeSet<String>,
+    /// The key-paths passed to WACC functions, in the order they appear
+    pub keys: Vec<KeyRef>,
+}
+
+impl Analysis {
+    /// Get the key-paths, resolving the branched ones against the context
+    pub fn key_paths(&self, context: &str) -> BTreeSet<String> {
+        self.keys
+            .iter()
+            .filter(|k| k.function != "_branch")
+            .map(|k| match k.branched {
+                true => format!("{context}{}", k.key),
+                false => k.key.clone(),
+            })
+            .collect()
+    }
+
+    /// Get the keys the WACC functions read, resolving the branched ones
+    /// against the context. Key-path prefixes are not included since there
+    /// may legitimately be no keys under them.
+    pub fn reads(&self, context: &str) -> Vec<Read> {
+        let mut reads = Vec::default();
+        for k in &self.keys {
+            let key = match k.branched {
+                true => format!("{context}{}", k.key),
+                false => k.key.clone(),
+            };
+
+            // find where the function reads the parameter from
+            let mut args = vec![String::default(); k.arg + 1];
+            args[k.arg] = key.clone();
+            let plan = api::prefetch(&k.function, &args);
+            let states = [(State::Current, plan.current_keys), (State::Proposed, plan.proposed_keys)];
+            for (state, keys) in states {
+                if keys.contains(&key) {
+                    let read = Read {
+                        state,
+                        key: key.clone(),
+                        function: k.function.clone(),
+                    };
+                    if !reads.contains(&read) {
+                        reads.push(read);
+                    }
+                }
+            }
+        }
+        reads
+    }
+}
+
+/// A value on the abstract operand stack
+#[derive(Clone, Debug)]
+enum Slot {
+    Const(i32),
+    /// The offset and length returned by _branch for the key
+    Branch(String),
+    Unknown,
+}
+
+/// The initialized linear memory
+#[derive(Default)]
+struct Data {
+    segments: BTreeMap<u32, Vec<u8>>,
+}
+
+impl Data {
+    fn read(&self, ptr: i32, len: i32) -> Option<String> {
+        let (ptr, len) = (ptr as u32, len as u32);
+        let mut buf = Vec::with_capacity(len as usize);
+        for addr in ptr..ptr.checked_add(len)? {
+            let (start, bytes) = self.segments.range(..=addr).next_back()?;
+            buf.push(*bytes.get((addr - start) as usize)?);
+        }
+        String::from_utf8(buf).ok()
+    }
+}
+
+/// Statically lists the WACC functions a script, in binary or text format,
+/// imports and the key-paths it passes to them. Key-paths are found where
+/// the offset and length parameters of a WACC call are i32.const values
+/// pointing into a data segment, or are the result of a _branch call on such
+/// a key-path.
+pub fn analyze(bytes: &[u8]) -> Result<Analysis, Error> {
+    let wasm = text::to_wasm(bytes)?;
+    let parse = |e: wasmparser::BinaryReaderError| Error::Wasmtime(e.to_string());
+
+    let mut analysis = Analysis::default();
+    let mut imports: Vec<Option<String>> = Vec::default();
+    let mut data = Data::default();
+    let mut bodies = Vec::default();
+
+    for payload in Parser::new(0).parse_all(&wasm) {
+        match payload.map_err(parse)? {
+            Payload::ImportSection(reader) => {
+                for import in reader {
+                    let import = import.map_err(parse)?;
+                    if let TypeRef::Func(_) = import.ty {
+                        let wacc = import.module == WACC_MODULE;
+                        if wacc {
+                            analysis.functions.insert(import.name.to_string());
+                        }
+                        imports.push(wacc.then(|| import.name.to_string()));
+                    }
+                }
+            }
+            Payload::DataSection(reader) => {
+                for segment in reader {
+                    let segment = segment.map_err(parse)?;
+                    if let DataKind::Active { offset_expr, .. } = segment.kind {
+                        if let Some(Ok(Operator::I32Const { value })) =
+                            offset_expr.get_operators_reader().into_iter().next()
+                        {
+                            data.segments.insert(value as u32, segment.data.to_vec());
+                        }
+                    }
+                }
+            }
+            Payload::CodeSectionEntry(body) => bodies.push(body),
+            _ => {}
+        }
+    }
+
+    // the data section comes after the code section so scan the code last
+    for body in bodies {
+        let mut stack: Vec<Slot> = Vec::default();
+        for op in body.get_operators_reader().map_err(parse)? {
+            let function_index = match op.map_err(parse)? {
+                Operator::I32Const { value } => {
+                    stack.push(Slot::Const(value));
+                    continue;
+                }
+                Operator::Call { function_index } => function_index,
+                _ => {
+                    // anything else makes the stack unknown
+                    stack.clear();
+                    continue;
+                }
+            };
+
+            // only calls to WACC functions are followed
+            let name = match imports.get(function_index as usize) {
+                Some(Some(name)) => name.clone(),
+                _ => {
+                    stack.clear();
+                    continue;
+                }
+            };
+            let (params, results) = match api::signature(&name) {
+                Some(sig) => sig,
+                None => {
+                    stack.clear();
+                    continue;
+                }
+            };
+
+            // decode the string parameters
+            let args = stack.split_off(stack.len().saturating_sub(params.len()));
+            let mut branched = None;
+            for (arg, pair) in args.chunks_exact(2).enumerate() {
+                let key = match pair {
+                    [Slot::Const(ptr), Slot::Const(len)] => {
+                        data.read(*ptr, *len).map(|k| (k, false))
+                    }
+                    [Slot::Branch(key), Slot::Unknown] => Some((key.clone(), true)),
+                    _ => None,
+                };
+                if let Some((key, is_branched)) = key {
+                    if name == "_branch" {
+                        branched = Some(key.clone());
+                    }
+                    analysis.keys.push(KeyRef {
+                        function: name.clone(),
+                        arg,
+                        key,
+                        branched: is_branched,
+                    });
+                }
+            }
+
+            // push the results, _branch returns the offset and length of the
+            // key-path in the branch context
+            match branched {
+                Some(key) => stack.extend([Slot::Branch(key), Slot::Unknown]),
+                None => stack.extend(results.iter().map(|_| Slot::Unknown)),
+            }
+        }
+    }
+
+    Ok(analysis)
+}
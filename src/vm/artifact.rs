@@ -0,0 +1,100 @@
+// SPDX-License-Identifier: FSL-1.1
+use crate::{error::VmError, vm::runtime::EngineOptions, Error};
+use sha2::{Digest, Sha256};
+use std::hash::{Hash, Hasher};
+use wasmtime::{Engine, Module, Precompiled};
+
+/// The magic bytes at the start of every compiled WACC artifact
+pub const MAGIC: &[u8; 4] = b"WACC";
+
+/// The version of the artifact header format
+pub const VERSION: u8 = 1;
+
+/// The length of the artifact header: magic, version, option flags and the
+/// engine configuration fingerprint
+pub const HEADER_LEN: usize = MAGIC.len() + 1 + 1 + 8;
+
+/// Feeds the hashed data into SHA-256 so the fingerprint doesn't depend on
+/// the standard library's unspecified hashing algorithm
+#[derive(Default)]
+struct Sha256Hasher(Sha256);
+
+impl Hasher for Sha256Hasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        let digest = self.0.clone().finalize();
+        let mut fp = [0u8; 8];
+        fp.copy_from_slice(&digest[..8]);
+        u64::from_le_bytes(fp)
+    }
+}
+
+/// Calculates a fingerprint of the engine configuration that determines if
+/// artifacts compiled by one engine can be loaded by another
+pub fn fingerprint(engine: &Engine) -> u64 {
+    let mut hasher = Sha256Hasher::default();
+    engine.precompile_compatibility_hash().hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Wraps the precompiled module with a header recording the engine
+/// configuration it was compiled with
+pub(crate) fn encode(engine: &Engine, options: &EngineOptions, aot: &[u8]) -> Vec<u8> {
+    let mut v = Vec::with_capacity(HEADER_LEN + aot.len());
+    v.extend_from_slice(MAGIC);
+    v.push(VERSION);
+    v.push(options.flags());
+    v.extend_from_slice(&fingerprint(engine).to_le_bytes());
+    v.extend_from_slice(aot);
+    v
+}
+
+/// Checks the artifact header against the engine configuration and returns
+/// the precompiled module bytes
+pub(crate) fn decode<'b>(engine: &Engine, options: &EngineOptions, bytes: &'b [u8]) -> Result<&'b [u8], Error> {
+    if bytes.len() < HEADER_LEN || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(VmError::InvalidArtifact("missing artifact header".to_string()).into());
+    }
+    let (header, aot) = bytes.split_at(HEADER_LEN);
+    if header[4] != VERSION {
+        return Err(VmError::InvalidArtifact(format!("unsupported artifact version {}", header[4])).into());
+    }
+    let flags = header[5] ^ options.flags();
+    if flags & 0x01 != 0 {
+        return Err(VmError::IncompatibleArtifact(format!(
+            "artifact compiled with fuel {}, engine has fuel {}",
+            if options.fuel { "disabled" } else { "enabled" },
+            if options.fuel { "enabled" } else { "disabled" },
+        )).into());
+    }
+    if flags & 0x02 != 0 {
+        return Err(VmError::IncompatibleArtifact("profile floating point rules mismatch".to_string()).into());
+    }
+    let mut fp = [0u8; 8];
+    fp.copy_from_slice(&header[6..]);
+    if u64::from_le_bytes(fp) != fingerprint(engine) {
+        return Err(VmError::IncompatibleArtifact("engine configuration mismatch".to_string()).into());
+    }
+    if engine.detect_precompiled(aot) != Some(Precompiled::Module) {
+        return Err(VmError::InvalidArtifact("not a precompiled module".to_string()).into());
+    }
+    Ok(aot)
+}
+
+/// Checks the artifact and deserializes the module from it
+///
+/// # Safety
+///
+/// The header checks only catch artifacts meant for another engine, they
+/// don't make the machine code safe to run. The bytes must be an artifact
+/// produced by [`encode`], or by a [`crate::vm::Compiler`], that has not been
+/// modified since by anyone but a trusted party.
+pub(crate) unsafe fn deserialize(engine: &Engine, options: &EngineOptions, bytes: &[u8]) -> Result<Module, Error> {
+    let aot = decode(engine, options, bytes)?;
+    // SAFETY: the caller guarantees the artifact comes from a trusted
+    // compilation, which is what Module::deserialize requires
+    unsafe { Module::deserialize(engine, aot).map_err(|e| Error::Wasmtime(e.to_string())) }
+}
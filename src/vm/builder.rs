@@ -1,14 +1,43 @@
 // SPDX-License-Identifier: FSL-1.1
-use crate::{api, error::VmError, Context, Error, Instance};
-use wasmtime::{Config, Engine, Linker, Module, Store};
+use crate::{
+    api::{self, Mode, Registrar},
+    error::VmError,
+    storage::{Local, Storage, Threaded},
+    vm::{artifact, instance::Interrupt, runtime::EngineOptions, text, Limits, Phase, Profile, Runtime},
+    Context, Error, Instance,
+};
+use std::{sync::Arc, time::Duration};
+use wasmtime::{Engine, Linker, Module, Store, UpdateDeadline};
 
-/// Builder type for constructing WacVm instances
+/// The format of the script bytes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Format {
+    /// Binary or text, detected from the bytes
+    #[default]
+    Any,
+    /// The wasm binary format
+    Binary,
+    /// The wasm text format
+    Text,
+    /// An artifact produced by the compiler
+    Precompiled,
+}
+
+/// Builder type for constructing WacVm instances. The storage kind follows
+/// the [`Context`], async executions need a context with [`Threaded`] storage.
 #[derive(Default)]
-pub struct Builder<'a>
+pub struct Builder<'a, M: Storage = Local>
 {
     fuel: Option<u64>,
+    deadline: Option<Duration>,
+    async_support: bool,
     bytes: Vec<u8>,
-    context: Option<Context<'a>>,
+    format: Format,
+    limits: Option<Limits>,
+    profile: Option<Profile>,
+    phase: Phase,
+    context: Option<Context<'a, M>>,
+    runtime: Option<&'a Runtime>,
 }
 
 impl<'a> Builder<'a>
@@ -17,41 +46,249 @@ impl<'a> Builder<'a>
     pub fn new() -> Self {
         Self {
             fuel: None,
+            deadline: None,
+            async_support: false,
             bytes: Vec::default(),
+            format: Format::Any,
+            limits: None,
+            profile: None,
+            phase: Phase::Any,
             context: None,
+            runtime: None,
         }
     }
 
+    /// Tries to build the [`Instance`] from the builder configuration
+    pub fn try_build(self) -> Result<Instance<'a>, Error> {
+        // the async host functions need storage that can be used from other threads
+        if self.async_support || self.runtime.is_some_and(|rt| rt.options().async_support) {
+            return Err(VmError::IncompatibleRuntime("async execution requires threaded storage".to_string()).into());
+        }
+        self.build()
+    }
+}
+
+impl<'a> Builder<'a, Threaded>
+{
+    /// Tries to build the [`Instance`] from the builder configuration
+    pub fn try_build(self) -> Result<Instance<'a, Threaded>, Error> {
+        self.build()
+    }
+}
+
+impl<'a, M: Storage> Builder<'a, M>
+{
     /// Enables the use of fuel and establishes the fuel limit for the execution
     pub fn with_fuel(mut self, fuel: u64) -> Self {
         self.fuel = Some(fuel);
         self
     }
 
-    /// Initializes the [`Instance`] with the bytes to execute
+    /// Establishes the wall-clock limit for the execution, measured from the
+    /// start of each run. This requires a [`Runtime`] with deadlines enabled.
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Builds an [`Instance`] that is executed with [`Instance::run_async`],
+    /// when using a [`Runtime`] it must have async support enabled
+    pub fn with_async(mut self) -> Self {
+        self.async_support = true;
+        self
+    }
+
+    /// Initializes the [`Instance`] with the bytes to execute, in the binary
+    /// format or, with the wat feature, the text format
     pub fn with_bytes(mut self, bytes: impl AsRef<[u8]>) -> Self {
         self.bytes = bytes.as_ref().to_vec();
+        self.format = Format::Any;
         self
     }
 
-    /// Add the context for the application state
-    pub fn with_context(mut self, context: Context<'a>) -> Self {
-        self.context = Some(context);
+    /// Initializes the [`Instance`] with a script in the wasm binary format,
+    /// building fails if it is in any other format
+    pub fn with_wasm(mut self, bytes: impl AsRef<[u8]>) -> Self {
+        self.bytes = bytes.as_ref().to_vec();
+        self.format = Format::Binary;
         self
     }
 
-    /// Tries to build the [`Instance`] from the builder configuration
-    pub fn try_build(self) -> Result<Instance<'a>, Error> {
-        // configure the engine
-        let mut config = Config::default();
-        config.consume_fuel(self.fuel.is_some());
-        let engine = Engine::new(&config).map_err(|e| Error::Wasmtime(e.to_string()))?;
+    /// Initializes the [`Instance`] with a script in the wasm text format,
+    /// building fails with the location of the first parse error
+    #[cfg(feature = "wat")]
+    pub fn with_wat(mut self, text: impl AsRef<str>) -> Self {
+        self.bytes = text.as_ref().as_bytes().to_vec();
+        self.format = Format::Text;
+        self
+    }
 
-        // try to compile the script
-        let aot = engine.precompile_module(&self.bytes).map_err(|e| Error::Wasmtime(e.to_string()))?;
+    /// Initializes the [`Instance`] with an artifact produced by
+    /// [`crate::vm::Compiler`]. The artifact must have been compiled with the
+    /// same engine configuration, including fuel, or building fails. The
+    /// artifact isn't checked against the [`Limits`], those of the compiler
+    /// applied when it was produced.
+    ///
+    /// # Safety
+    ///
+    /// The artifact is deserialized into executable machine code without
+    /// being validated, the header only guards against engine mismatches. It
+    /// must come from a trusted [`crate::vm::Compiler`] and not have been
+    /// modified since.
+    pub unsafe fn with_precompiled(mut self, bytes: impl AsRef<[u8]>) -> Self {
+        self.bytes = bytes.as_ref().to_vec();
+        self.format = Format::Precompiled;
+        self
+    }
+
+    /// Sets the static limits the script is checked against before it is
+    /// compiled. They default to [`Limits::default`] without a [`Runtime`]
+    /// and to the runtime's limits with one. The runtime caches the compiled
+    /// module per limits, so a cached script isn't parsed again. Precompiled
+    /// artifacts aren't checked, the [`crate::vm::Compiler`] checks its
+    /// limits when it produces them.
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = Some(limits);
+        self
+    }
 
-        // configure the module
-        let module = unsafe { Module::deserialize(&engine, aot).map_err(|e| Error::Wasmtime(e.to_string()))? };
+    /// Sets the execution profile, when using a [`Runtime`] this must match
+    /// the runtime profile
+    pub fn with_profile(mut self, profile: Profile) -> Self {
+        self.profile = Some(profile);
+        self
+    }
+
+    /// Sets the execution phase, only the WACC functions available in the
+    /// phase can be imported by the script
+    pub fn with_phase(mut self, phase: Phase) -> Self {
+        self.phase = phase;
+        self
+    }
+
+    /// Add the context for the application state, the builder takes on the
+    /// storage kind of the context
+    pub fn with_context<N: Storage>(self, context: Context<'a, N>) -> Builder<'a, N> {
+        Builder {
+            fuel: self.fuel,
+            deadline: self.deadline,
+            async_support: self.async_support,
+            bytes: self.bytes,
+            format: self.format,
+            limits: self.limits,
+            profile: self.profile,
+            phase: self.phase,
+            context: Some(context),
+            runtime: self.runtime,
+        }
+    }
+
+    /// Use the shared [`Runtime`] engine and compiled module cache instead of
+    /// creating a new engine and compiling the script for this instance
+    pub fn with_runtime(mut self, runtime: &'a Runtime) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+}
+
+impl<'a, M: Registrar> Builder<'a, M>
+{
+    fn build(self) -> Result<Instance<'a, M>, Error> {
+        // reject large scripts before converting them
+        let limits = match self.runtime {
+            Some(rt) => self.limits.unwrap_or(rt.limits()),
+            None => self.limits.unwrap_or_default(),
+        };
+        if self.format != Format::Precompiled {
+            limits.check_size(&self.bytes)?;
+        }
+
+        // convert scripts in the text format to the binary format
+        let bytes = match self.format {
+            Format::Any => text::to_wasm(&self.bytes)?,
+            Format::Binary => text::binary(&self.bytes)?,
+            Format::Text => text::parse(&self.bytes)?,
+            Format::Precompiled => self.bytes.as_slice().into(),
+        };
+
+        let (engine, module, fuel, epochs, mode) = match self.runtime {
+            Some(rt) => {
+                // the runtime engine must be able to meter fuel
+                if self.fuel.is_some() && !rt.fuel() {
+                    return Err(VmError::IncompatibleRuntime("fuel is not enabled".to_string()).into());
+                }
+
+                // the runtime must have a ticker to enforce deadlines
+                if self.deadline.is_some() && rt.epoch_tick().is_none() {
+                    return Err(VmError::IncompatibleRuntime("deadlines are not enabled".to_string()).into());
+                }
+
+                // the runtime engine must support async execution
+                if self.async_support && !rt.options().async_support {
+                    return Err(VmError::IncompatibleRuntime("async support is not enabled".to_string()).into());
+                }
+
+                // the runtime engine must have the same profile
+                if self.profile.is_some_and(|p| p != rt.options().profile) {
+                    return Err(VmError::IncompatibleRuntime("profile mismatch".to_string()).into());
+                }
+
+                // get the module from the cache, compiling it if needed
+                let module = if self.format == Format::Precompiled {
+                    // SAFETY: the caller of with_precompiled vouched for the artifact
+                    unsafe { rt.precompiled(&bytes)? }
+                } else {
+                    rt.checked_module(&bytes, limits)?
+                };
+
+                // an engine consuming fuel needs a budget even if none was given
+                let fuel = match self.fuel {
+                    Some(fuel) => Some(fuel),
+                    None if rt.fuel() => Some(u64::MAX),
+                    None => None,
+                };
+
+                let mode = match rt.options().async_support {
+                    true => Mode::Async,
+                    false => Mode::Sync,
+                };
+
+                (rt.engine().clone(), module, fuel, rt.epoch_tick().is_some(), mode)
+            }
+            None => {
+                // deadlines are enforced by the runtime ticker
+                if self.deadline.is_some() {
+                    return Err(VmError::IncompatibleRuntime("deadlines require a runtime".to_string()).into());
+                }
+
+                let options = EngineOptions {
+                    fuel: self.fuel.is_some(),
+                    profile: self.profile.unwrap_or_default(),
+                    epochs: false,
+                    async_support: self.async_support,
+                };
+
+                // configure the engine
+                let engine = Engine::new(&options.config()).map_err(|e| Error::Wasmtime(e.to_string()))?;
+
+                // configure the module
+                let module = if self.format == Format::Precompiled {
+                    // SAFETY: the caller of with_precompiled vouched for the artifact
+                    unsafe { artifact::deserialize(&engine, &options, &bytes)? }
+                } else {
+                    limits.check(&bytes)?;
+                    options.profile.validate(&bytes)?;
+                    Module::from_binary(&engine, &bytes).map_err(|e| Error::Wasmtime(e.to_string()))?
+                };
+
+                let mode = match self.async_support {
+                    true => Mode::Async,
+                    false => Mode::Sync,
+                };
+
+                (engine, module, self.fuel, false, mode)
+            }
+        };
 
         // get the context
         let context = match self.context {
@@ -61,12 +298,28 @@ impl<'a> Builder<'a>
 
         // configure the store
         let mut store = Store::new(&engine, context);
-        if let Some(fuel) = self.fuel {
+        if let Some(fuel) = fuel {
             store
                 .set_fuel(fuel)
                 .map_err(|e| Error::Wasmtime(e.to_string()))?;
         }
 
+        // check for cancellation and the deadline on every epoch tick
+        let interrupt = match epochs {
+            true => {
+                let interrupt = Arc::new(Interrupt::default());
+                let state = interrupt.clone();
+                let deadline = self.deadline;
+                store.set_epoch_deadline(1);
+                store.epoch_deadline_callback(move |_| {
+                    state.check(deadline)?;
+                    Ok(UpdateDeadline::Continue(1))
+                });
+                Some(interrupt)
+            }
+            false => None,
+        };
+
         // configure the limiter
         store.limiter(|state| &mut state.limiter);
 
@@ -74,13 +327,28 @@ impl<'a> Builder<'a>
         let mut linker = Linker::new(&engine);
 
         // add the Wacc functions
-        api::add_to_linker(&engine, &mut linker)?;
+        api::add_to_linker(&engine, &mut linker, mode, self.phase)?;
+
+        // make sure every WACC function the script imports is available
+        for import in module.imports() {
+            if import.module() == "wacc" && linker.get_by_import(&mut store, &import).is_none() {
+                return Err(VmError::UnavailableImport {
+                    phase: self.phase,
+                    name: import.name().to_string(),
+                }
+                .into());
+            }
+        }
 
         // build the instance
         Ok(Instance {
             linker,
             module,
             store,
+            budget: fuel,
+            deadline: self.deadline,
+            interrupt,
+            mode,
         })
     }
 }
@@ -0,0 +1,139 @@
+// SPDX-License-Identifier: FSL-1.1
+use crate::{error::VmError, vm::{artifact, runtime::EngineOptions}, Error};
+use hmac::{Hmac, Mac};
+use multihash::Multihash;
+use sha2::Sha256;
+use std::{
+    fmt, fs,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use wasmtime::{Engine, Module};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// The file extension used for cached artifacts
+pub const EXTENSION: &str = "wacc";
+
+/// The length of the HMAC-SHA256 tag in front of each cached artifact
+const TAG_LEN: usize = 32;
+
+static TMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// A persistent cache directory of compiled WACC modules. Each entry is keyed
+/// by the script Multihash and the engine configuration fingerprint and
+/// stores an HMAC-SHA256 tag of the entry name and the artifact that is
+/// checked before the artifact is deserialized. Without the key an entry can't
+/// be forged or moved to another name, so the key must be kept secret and
+/// outside the cache directory.
+#[derive(Clone)]
+pub struct DiskCache {
+    dir: PathBuf,
+    key: Vec<u8>,
+}
+
+impl fmt::Debug for DiskCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DiskCache").field("dir", &self.dir).finish_non_exhaustive()
+    }
+}
+
+impl DiskCache {
+    /// Opens the cache directory, creating it if it doesn't exist. The key
+    /// authenticates the entries.
+    pub fn new(dir: impl AsRef<Path>, key: impl AsRef<[u8]>) -> Result<Self, Error> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir).map_err(|e| Error::custom(&e))?;
+        Ok(Self { dir, key: key.as_ref().to_vec() })
+    }
+
+    /// Get the path of the cache entry for the script
+    pub fn path(&self, script: &Multihash, engine: &Engine, options: &EngineOptions) -> PathBuf {
+        let script: Vec<u8> = script.clone().into();
+        let name = format!(
+            "{}-{:016x}{:02x}.{EXTENSION}",
+            to_hex(&script),
+            artifact::fingerprint(engine),
+            options.flags()
+        );
+        self.dir.join(name)
+    }
+
+    /// Get the HMAC of the entry name and the artifact
+    fn mac(&self, path: &Path, data: &[u8]) -> Result<HmacSha256, Error> {
+        let mut mac = HmacSha256::new_from_slice(&self.key).map_err(|e| Error::custom(&e))?;
+        let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+        mac.update(name.as_bytes());
+        mac.update(data);
+        Ok(mac)
+    }
+
+    /// Tries to load the module for the script from the cache. Returns None
+    /// if there is no entry and an error if the entry fails authentication.
+    ///
+    /// # Safety
+    ///
+    /// The entry is deserialized into executable machine code once its tag is
+    /// verified. The key must be kept secret, anyone who knows it can forge
+    /// entries.
+    pub unsafe fn get(&self, script: &Multihash, engine: &Engine, options: &EngineOptions) -> Result<Option<Module>, Error> {
+        let path = self.path(script, engine, options);
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(Error::custom(&e)),
+        };
+
+        // split off the tag: hmac-sha256 || artifact
+        if bytes.len() < TAG_LEN {
+            return Err(VmError::CorruptCache(path.display().to_string()).into());
+        }
+        let (tag, data) = bytes.split_at(TAG_LEN);
+
+        // authenticate the entry before deserializing anything
+        self.mac(&path, data)?
+            .verify_slice(tag)
+            .map_err(|_| VmError::CorruptCache(path.display().to_string()))?;
+
+        // SAFETY: the entry was written by a holder of the key, which the
+        // caller guarantees is secret
+        unsafe { artifact::deserialize(engine, options, data).map(Some) }
+    }
+
+    /// Atomically writes the artifact for the script to the cache
+    pub fn put(&self, script: &Multihash, engine: &Engine, options: &EngineOptions, data: &[u8]) -> Result<(), Error> {
+        let path = self.path(script, engine, options);
+        let tag = self.mac(&path, data)?.finalize().into_bytes();
+
+        // write to a temporary file in the same directory then rename it
+        let tmp = path.with_extension(format!(
+            "{EXTENSION}.{}.{}.tmp",
+            std::process::id(),
+            TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let write = || -> std::io::Result<()> {
+            let mut f = fs::File::create(&tmp)?;
+            f.write_all(&tag)?;
+            f.write_all(data)?;
+            f.sync_all()?;
+            fs::rename(&tmp, &path)
+        };
+        write().map_err(|e| {
+            let _ = fs::remove_file(&tmp);
+            Error::custom(&e)
+        })
+    }
+
+    /// Removes the cache entry for the script
+    pub fn remove(&self, script: &Multihash, engine: &Engine, options: &EngineOptions) -> Result<(), Error> {
+        match fs::remove_file(self.path(script, engine, options)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(Error::custom(&e)),
+            _ => Ok(()),
+        }
+    }
+}
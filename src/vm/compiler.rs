@@ -1,11 +1,13 @@
 // SPDX-License-Identifier: FSL-1.1
-use crate::Error;
-use wasmtime::{Config, Engine};
+use crate::{vm::{artifact, runtime::EngineOptions, text, Limits, Profile}, Error};
+use wasmtime::Engine;
 
 /// Compiler type for compiling wasm scripts
 #[derive(Default)]
 pub struct Compiler
 {
+    options: EngineOptions,
+    limits: Limits,
     bytes: Vec<u8>,
 }
 
@@ -14,23 +16,62 @@ impl Compiler
     /// create a new builder
     pub fn new() -> Self {
         Self {
+            options: EngineOptions::default(),
+            limits: Limits::default(),
             bytes: Vec::default(),
         }
     }
 
+    /// Compiles the script for an engine that consumes fuel, this must match
+    /// the fuel setting of the [`crate::vm::Builder`] loading the artifact
+    pub fn with_fuel(mut self, fuel: bool) -> Self {
+        self.options.fuel = fuel;
+        self
+    }
+
+    /// Compiles the script with the execution profile, this must match the
+    /// profile of the [`crate::vm::Builder`] loading the artifact
+    pub fn with_profile(mut self, profile: Profile) -> Self {
+        self.options.profile = profile;
+        self
+    }
+
+    /// Compiles the script for an engine with deadlines enabled, this must
+    /// match the [`crate::vm::Runtime`] loading the artifact
+    pub fn with_deadlines(mut self, deadlines: bool) -> Self {
+        self.options.epochs = deadlines;
+        self
+    }
+
+    /// Sets the static limits the script is checked against before compiling
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
     /// Initializes the [`Compiler`] with the bytes to execute
     pub fn with_bytes(mut self, bytes: impl AsRef<[u8]>) -> Self {
         self.bytes = bytes.as_ref().to_vec();
         self
     }
 
-    /// Tries to build the [`Instance`] from the builder configuration
+    /// Tries to compile the script into an artifact that can be loaded with
+    /// [`crate::vm::Builder::with_precompiled`]
     pub fn try_compile(self) -> Result<Vec<u8>, Error> {
+        // validate the script against the limits and the profile
+        self.limits.check_size(&self.bytes)?;
+        let wasm = text::to_wasm(&self.bytes)?;
+        self.limits.check(&wasm)?;
+        self.options.profile.validate(&wasm)?;
+
         // configure the engine
-        let config = Config::default();
+        let config = self.options.config();
         let engine = Engine::new(&config).map_err(|e| Error::Wasmtime(e.to_string()))?;
 
         // try to compile the script
-        engine.precompile_module(&self.bytes).map_err(|e| Error::Wasmtime(e.to_string()))
+        let aot = engine.precompile_module(&wasm).map_err(|e| Error::Wasmtime(e.to_string()))?;
+
+        // wrap it with the engine configuration header
+        Ok(artifact::encode(&engine, &self.options, &aot))
     }
 }
@@ -1,49 +1,294 @@
 // SPDX-License-Identifier: FSL-1.1
 use crate::{
     api::{WASM_FALSE, WASM_TRUE},
-    Pairs, Stack, Value,
+    error::VmError,
+    storage::{Local, Storage, Threaded},
+    vm::{aggregate, CostSchedule, Meter, SignaturePolicy, SignedMessage},
+    Error, Pairs, Stack, Value,
 };
 use log::info;
 use multihash::{mh, Multihash};
 use multikey::{Multikey, Views};
 use multisig::Multisig;
 use multiutil::CodecInfo;
-use std::{fmt, io::Write};
-use wasmtime::{StoreLimits, Val};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    io::Write,
+};
+use wasmtime::{StoreLimits, StoreLimitsBuilder, Val};
+
+/// The default linear memory limit for each script
+pub const DEFAULT_MEMORY_LIMIT: usize = 1 << 22; /* 4MB */
 
 /// Represents the application state for each instance of a WACC execution.
-pub struct Context<'a>
+/// The storage is [`Local`] for sync executions and [`Threaded`] for async
+/// executions.
+pub struct Context<'a, M: Storage = Local>
 {
     /// The key-value store of the current state
-    pub current: &'a dyn Pairs,
+    pub current: &'a M::Pairs<'a>,
     /// The key-value store of the proposed state update
-    pub proposed: &'a dyn Pairs,
+    pub proposed: &'a M::Pairs<'a>,
     /// The stack of values
-    pub pstack: &'a mut dyn Stack,
+    pub pstack: &'a mut M::Stack<'a>,
     /// The stack of return values
-    pub rstack: &'a mut dyn Stack,
+    pub rstack: &'a mut M::Stack<'a>,
     /// The number of times a check_* operation has been executed
-    pub check_count: usize,
+    pub(crate) check_count: usize,
     /// The top down stack index for writing into linear memory
-    pub write_idx: usize,
+    pub(crate) write_idx: usize,
     /// The context key-path
     pub context: String,
     /// In-memory buffer to accumulate log messages from scripts
-    pub log: Vec<u8>,
+    pub(crate) log: Vec<u8>,
     /// The limiter
     pub limiter: StoreLimits,
+    /// The signature algorithm policy enforced by check_signature
+    pub policy: SignaturePolicy,
+    /// The fuel meter for the host functions
+    pub(crate) meter: Meter,
+    /// The record of every check_* operation executed, in order
+    pub(crate) checks: Vec<Check>,
+    /// The record of every key-path read and branch expansion
+    pub(crate) access: AccessSet,
+}
+
+/// The record of a check_* operation
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Check {
+    /// The name of the check function
+    pub function: String,
+    /// The key-paths the check was given
+    pub keys: Vec<String>,
+    /// If the check passed
+    pub passed: bool,
+    /// The reason the check failed
+    pub reason: Option<String>,
+}
+
+/// The record of the state a run read. A verdict only depends on the values
+/// associated with these keys, and the keys under these prefixes.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AccessSet {
+    /// The keys read from the current state
+    pub current: BTreeSet<String>,
+    /// The key-path prefixes listed in the current state
+    pub current_prefixes: BTreeSet<String>,
+    /// The keys read from the proposed state
+    pub proposed: BTreeSet<String>,
+    /// The branch expansions, from the key to the full key-path
+    pub branches: BTreeMap<String, String>,
+}
+
+/// Builder type for constructing a [`Context`]
+pub struct ContextBuilder<'a, M: Storage = Local> {
+    current: Option<&'a M::Pairs<'a>>,
+    proposed: Option<&'a M::Pairs<'a>>,
+    pstack: Option<&'a mut M::Stack<'a>>,
+    rstack: Option<&'a mut M::Stack<'a>>,
+    context: String,
+    memory_limit: usize,
+    limiter: Option<StoreLimits>,
+    policy: SignaturePolicy,
+    costs: CostSchedule,
+}
+
+impl<M: Storage> Default for ContextBuilder<'_, M> {
+    fn default() -> Self {
+        Self {
+            current: None,
+            proposed: None,
+            pstack: None,
+            rstack: None,
+            context: "/".to_string(),
+            memory_limit: DEFAULT_MEMORY_LIMIT,
+            limiter: None,
+            policy: SignaturePolicy::default(),
+            costs: CostSchedule::default(),
+        }
+    }
 }
 
-impl fmt::Debug for Context<'_> {
+impl<'a, M: Storage> ContextBuilder<'a, M> {
+    /// Set the key-value store of the current state
+    pub fn with_current(mut self, current: &'a M::Pairs<'a>) -> Self {
+        self.current = Some(current);
+        self
+    }
+
+    /// Set the key-value store of the proposed state update
+    pub fn with_proposed(mut self, proposed: &'a M::Pairs<'a>) -> Self {
+        self.proposed = Some(proposed);
+        self
+    }
+
+    /// Set the parameter stack
+    pub fn with_pstack(mut self, pstack: &'a mut M::Stack<'a>) -> Self {
+        self.pstack = Some(pstack);
+        self
+    }
+
+    /// Set the return stack
+    pub fn with_rstack(mut self, rstack: &'a mut M::Stack<'a>) -> Self {
+        self.rstack = Some(rstack);
+        self
+    }
+
+    /// Set the context key-path, defaults to "/". It must start and end with
+    /// a '/' since branch appends keys to it.
+    pub fn with_context(mut self, context: &str) -> Self {
+        self.context = context.to_string();
+        self
+    }
+
+    /// Set the linear memory limit, defaults to [`DEFAULT_MEMORY_LIMIT`]. The
+    /// script is limited to one memory and two instances.
+    pub fn with_memory_limit(mut self, limit: usize) -> Self {
+        self.memory_limit = limit;
+        self
+    }
+
+    /// Set the store limiter, replacing the one built from the memory limit
+    pub fn with_limiter(mut self, limiter: StoreLimits) -> Self {
+        self.limiter = Some(limiter);
+        self
+    }
+
+    /// Set the signature algorithm policy
+    pub fn with_policy(mut self, policy: SignaturePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Set the fuel cost schedule of the WACC functions
+    pub fn with_cost_schedule(mut self, costs: CostSchedule) -> Self {
+        self.costs = costs;
+        self
+    }
+
+    /// Tries to build the [`Context`], the state and the stacks are required.
+    /// A missing state fails with [`VmError::MissingContext`] and a missing
+    /// stack with [`VmError::MissingStack`].
+    pub fn try_build(self) -> Result<Context<'a, M>, Error> {
+        let current = self.current.ok_or(VmError::MissingContext)?;
+        let proposed = self.proposed.ok_or(VmError::MissingContext)?;
+        let pstack = self.pstack.ok_or(VmError::MissingStack)?;
+        let rstack = self.rstack.ok_or(VmError::MissingStack)?;
+
+        // the context key-path is absolute and ends with a separator
+        if !self.context.starts_with('/') || !self.context.ends_with('/') || self.context.contains("//") {
+            return Err(VmError::InvalidKeyPath(self.context).into());
+        }
+
+        let limiter = match self.limiter {
+            Some(limiter) => limiter,
+            None => StoreLimitsBuilder::new()
+                .memory_size(self.memory_limit)
+                .instances(2)
+                .memories(1)
+                .build(),
+        };
+
+        Ok(Context {
+            current,
+            proposed,
+            pstack,
+            rstack,
+            check_count: 0,
+            write_idx: 0,
+            context: self.context,
+            log: Vec::default(),
+            limiter,
+            policy: self.policy,
+            meter: Meter::new(self.costs),
+            checks: Vec::default(),
+            access: AccessSet::default(),
+        })
+    }
+}
+
+impl<M: Storage> fmt::Debug for Context<'_, M> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "Context {{ check_count: {}, context: {} }}", self.check_count, self.context)
     }
 }
 
-impl Context<'_> {
+impl<'a> Context<'a> {
+    /// Get a builder for a context with [`Local`] storage
+    pub fn builder() -> ContextBuilder<'a> {
+        ContextBuilder::default()
+    }
+}
+
+impl<'a> Context<'a, Threaded> {
+    /// Get a builder for a context with [`Threaded`] storage, which async
+    /// executions require
+    pub fn threaded_builder() -> ContextBuilder<'a, Threaded> {
+        ContextBuilder::default()
+    }
+}
+
+impl<'a, M: Storage> Context<'a, M> {
+    /// Get the number of check_* operations executed
+    pub fn check_count(&self) -> usize {
+        self.check_count
+    }
+
+    /// Get the log messages accumulated from the script
+    pub fn logged(&self) -> &[u8] {
+        &self.log
+    }
+
+    /// Get the fuel meter for the host functions
+    pub fn meter(&self) -> &Meter {
+        &self.meter
+    }
+
+    /// Get the record of every check_* operation executed, in order
+    pub fn checks(&self) -> &[Check] {
+        &self.checks
+    }
+
+    /// Get the record of every key-path read and branch expansion
+    pub fn access(&self) -> &AccessSet {
+        &self.access
+    }
+
+    /// Get a value from the current state, recording the access
+    pub fn get_current(&mut self, key: &str) -> Option<Value> {
+        self.access.current.insert(key.to_string());
+        self.current.get(key)
+    }
+
+    /// Get a value from the proposed state, recording the access
+    pub fn get_proposed(&mut self, key: &str) -> Option<Value> {
+        self.access.proposed.insert(key.to_string());
+        self.proposed.get(key)
+    }
+
+    /// List the keys in the current state with the prefix, recording the access
+    pub fn current_keys(&mut self, prefix: &str) -> Vec<String> {
+        self.access.current_prefixes.insert(prefix.to_string());
+        self.current.keys(prefix)
+    }
+
+    /// Record the start of a check_* operation
+    pub(crate) fn begin_check(&mut self, function: &str, keys: &[&str]) {
+        self.checks.push(Check {
+            function: function.to_string(),
+            keys: keys.iter().map(|k| k.to_string()).collect(),
+            passed: false,
+            reason: None,
+        });
+    }
 
     /// Increment the check counter and to push a FAILURE marker on the return stack
-    pub fn check_fail(&mut self, err: &str) -> Val {
+    pub(crate) fn check_fail(&mut self, err: &str) -> Val {
+        // record the failure reason
+        if let Some(check) = self.checks.last_mut() {
+            check.reason = Some(err.to_string());
+        }
         // update the context check_count
         self.check_count += 1;
         // fail
@@ -51,7 +296,7 @@ impl Context<'_> {
     }
 
     /// Increment the check counter and to push a FAILURE marker on the return stack
-    pub fn fail(&mut self, err: &str) -> Val {
+    pub(crate) fn fail(&mut self, err: &str) -> Val {
         // push the FAILURE onto the return stack
         self.rstack.push(Value::Failure(err.to_string()));
         // return that we failed
@@ -59,7 +304,11 @@ impl Context<'_> {
     }
 
     /// Push a SUCCESS marker onto the return stack
-    pub fn succeed(&mut self) -> Val {
+    pub(crate) fn succeed(&mut self) -> Val {
+        // record the success
+        if let Some(check) = self.checks.last_mut() {
+            check.passed = true;
+        }
         // push the SUCCESS marker with the check count
         self.rstack.push(self.check_count.into());
         // return that we succeeded
@@ -78,7 +327,7 @@ impl Context<'_> {
     /// Push the value associated with the key onto the parameter stack
     pub fn push(&mut self, key: &str) -> Val {
         // try to look up the key-value pair by key and push the result onto the stack
-        match self.current.get(key) {
+        match self.get_current(key) {
             Some(v) => {
                 self.pstack.push(v.clone()); // pushes Value::Bin(Vec<u8>)
                 WASM_TRUE
@@ -106,12 +355,20 @@ impl Context<'_> {
         s
     }
 
+    /// Calculate the full key given the context, recording the expansion
+    pub(crate) fn expand_branch(&mut self, key: &str) -> String {
+        let s = self.branch(key);
+        self.access.branches.insert(key.to_string(), s.clone());
+        s
+    }
+
     /// Verifies the top of the stack matches the value associated with the key
     pub fn check_eq(&mut self, key: &str) -> Val {
+        self.begin_check("check_eq", &[key]);
         info!("check_eq: loading from current {key}");
         // look up the value
         let value = {
-            match self.current.get(key) {
+            match self.get_current(key) {
                 Some(v @ Value::Bin { .. }) => v,
                 Some(v @ Value::Str { .. }) => v,
                 Some(_) => return self.check_fail(&format!("unexpected value type associated with {key}")),
@@ -149,9 +406,10 @@ impl Context<'_> {
 
     /// Checks the preimage proof against the hash already committed to
     pub fn check_preimage(&mut self, key: &str) -> Val {
+        self.begin_check("check_preimage", &[key]);
         // look up the hash and try to decode it
         let hash = {
-            match self.current.get(key) {
+            match self.get_current(key) {
                 Some(Value::Bin { hint: _, data }) => match Multihash::try_from(data.as_ref()) {
                     Ok(hash) => hash,
                     Err(e) => return self.check_fail(&e.to_string()),
@@ -168,6 +426,13 @@ impl Context<'_> {
             );
         }
 
+        // hashing the preimage costs fuel
+        match self.pstack.top() {
+            Some(Value::Bin { hint: _, data }) => self.meter.charge_hash(data.len()),
+            Some(Value::Str { hint: _, data }) => self.meter.charge_hash(data.len()),
+            _ => {}
+        }
+
         // get the preimage data from the stack
         let preimage = {
             match self.pstack.top() {
@@ -204,10 +469,17 @@ impl Context<'_> {
 
     /// Verifies the digital signature proof with the public key and message already committed to
     pub fn check_signature(&mut self, key: &str, msg: &str) -> Val {
+        self.check_signature_with(key, msg, &SignedMessage::Raw)
+    }
+
+    /// Verifies the digital signature proof with the public key already committed to over the
+    /// message constructed from the value associated with msg
+    pub fn check_signature_with(&mut self, key: &str, msg: &str, format: &SignedMessage) -> Val {
+        self.begin_check("check_signature", &[key, msg]);
         info!("check_signature: loading from current {key}");
         // look up the pubkey and try to decode it
         let pubkey = {
-            match self.current.get(key) {
+            match self.get_current(key) {
                 Some(Value::Bin { hint:_, data }) => match Multikey::try_from(data.as_ref()) {
                     Ok(mk) => mk,
                     Err(e) => return self.check_fail(&e.to_string()),
@@ -220,7 +492,7 @@ impl Context<'_> {
         // look up the message that was signed
         info!("check_signature: loading from proposed {msg}");
         let message = {
-            match self.proposed.get(msg) {
+            match self.get_proposed(msg) {
                 Some(Value::Bin { hint:_, data }) => data,
                 Some(Value::Str { hint: _, data }) => data.as_bytes().to_vec(),
                 Some(_) => return self.check_fail(&format!("unexpected value type associated with {msg}")),
@@ -228,6 +500,17 @@ impl Context<'_> {
             }
         };
 
+        // hashing the message costs fuel
+        if let SignedMessage::Hashed(_) = format {
+            self.meter.charge_hash(message.len());
+        }
+
+        // construct the signed message from the value
+        let message = match format.encode(&self.context, msg, &message) {
+            Ok(m) => m,
+            Err(e) => return self.check_fail(&e.to_string()),
+        };
+
         // make sure we have at least one parameters on the stack
         if self.pstack.is_empty() {
             return self.check_fail(
@@ -247,6 +530,12 @@ impl Context<'_> {
             }
         };
 
+        // enforce the signature policy before verifying anything
+        if let Err(e) = self.policy.check(&pubkey, &sig) {
+            info!("check_signature({key}, {msg}) -> policy violation");
+            return self.check_fail(&e.to_string());
+        }
+
         let verify_view = match pubkey.verify_view() {
             Ok(v) => v,
             Err(e) => return self.check_fail(&e.to_string()),
@@ -267,4 +556,190 @@ impl Context<'_> {
             }
         }
     }
+
+    /// Verifies an aggregated signature proof with the public keys committed to under the
+    /// key-path prefix. The message is either shared, when a value is associated with msg, or
+    /// per-signer, associated with msg followed by the part of each public key-path after
+    /// the prefix. Proof-of-possession aggregates are rejected because the public keys do
+    /// not come with proofs, so a shared message must be signed with message augmentation.
+    pub fn check_aggregate_signature(&mut self, prefix: &str, msg: &str) -> Val {
+        self.begin_check("check_aggregate_signature", &[prefix, msg]);
+        info!("check_aggregate_signature: loading from current {prefix}");
+        // look up the pubkeys and try to decode them
+        let keys = self.current_keys(prefix);
+        if keys.is_empty() {
+            return self.check_fail(&format!("no multikeys associated with {prefix}"));
+        }
+        let mut pubkeys = Vec::with_capacity(keys.len());
+        for key in &keys {
+            // every signer costs fuel, stop once it runs out
+            self.meter.charge(self.meter.schedule.aggregate_signer);
+            if self.meter.exhausted() {
+                return self.check_fail("out of fuel");
+            }
+            match self.get_current(key) {
+                Some(Value::Bin { hint: _, data }) => match Multikey::try_from(data.as_ref()) {
+                    Ok(mk) => pubkeys.push(mk),
+                    Err(e) => return self.check_fail(&e.to_string()),
+                },
+                Some(_) => return self.check_fail(&format!("unexpected value type associated with {key}")),
+                None => return self.check_fail(&format!("no multikey associated with {key}")),
+            }
+        }
+
+        // look up the messages that were signed
+        info!("check_aggregate_signature: loading from proposed {msg}");
+        let shared = self.get_proposed(msg);
+        let mut messages = Vec::with_capacity(keys.len());
+        for key in &keys {
+            let Some(suffix) = key.strip_prefix(prefix) else {
+                return self.check_fail(&format!("{key} does not start with {prefix}"));
+            };
+            let msg_key = format!("{msg}{suffix}");
+            let value = match &shared {
+                Some(v) => Some(v.clone()),
+                None => self.get_proposed(&msg_key),
+            };
+            match value {
+                Some(Value::Bin { hint: _, data }) => messages.push(data),
+                Some(Value::Str { hint: _, data }) => messages.push(data.as_bytes().to_vec()),
+                Some(_) => return self.check_fail(&format!("unexpected value type associated with {msg_key}")),
+                None => return self.check_fail(&format!("no message associated with {msg_key}")),
+            }
+        }
+
+        // make sure we have at least one parameters on the stack
+        if self.pstack.is_empty() {
+            return self.check_fail(
+                &format!("not enough parameters ({}) on the stack for check_aggregate_signature ({prefix}, {msg})", self.pstack.len())
+            );
+        }
+
+        // peek at the top item and verify that it is a Multisig
+        info!("check_aggregate_signature: loading sig from stack");
+        let sig = {
+            match self.pstack.top() {
+                Some(Value::Bin { hint: _, data }) => match Multisig::try_from(data.as_ref()) {
+                    Ok(sig) => sig,
+                    Err(e) => return self.check_fail(&e.to_string()),
+                },
+                _ => return self.check_fail("no multisig on stack"),
+            }
+        };
+
+        // enforce the signature policy for every signer
+        for pubkey in &pubkeys {
+            if let Err(e) = self.policy.check(pubkey, &sig) {
+                info!("check_aggregate_signature({prefix}, {msg}) -> policy violation");
+                return self.check_fail(&e.to_string());
+            }
+        }
+
+        // verify the aggregated signature
+        match aggregate::verify_aggregate(&pubkeys, &sig, &messages) {
+            Ok(_) => {
+                info!("check_aggregate_signature({prefix}, {msg}) -> true");
+                // the signature verification worked so pop the signature argument off
+                // of the stack before continuing
+                self.pstack.pop();
+                self.succeed()
+            }
+            Err(e) => {
+                info!("check_aggregate_signature({prefix}, {msg}) -> false");
+                self.check_fail(&e.to_string())
+            }
+        }
+    }
+
+    /// Verifies the nonce in the proposed state has not already been used, that is, it is not
+    /// equal to any value committed to under the used-nonce key-path prefix
+    pub fn check_fresh_nonce(&mut self, key: &str, prefix: &str) -> Val {
+        self.begin_check("check_fresh_nonce", &[key, prefix]);
+        info!("check_fresh_nonce: loading from proposed {key}");
+        // look up the proposed nonce, only the payload bytes count so a replayed nonce
+        // can't be disguised with a different hint or value type
+        let nonce = {
+            match self.get_proposed(key) {
+                Some(Value::Bin { hint: _, data }) => data,
+                Some(Value::Str { hint: _, data }) => data.into_bytes(),
+                Some(_) => return self.check_fail(&format!("unexpected value type associated with {key}")),
+                None => return self.check_fail(&format!("no nonce associated with {key}")),
+            }
+        };
+
+        // compare it against all of the used nonces
+        info!("check_fresh_nonce: loading used nonces from current {prefix}");
+        for used in self.current_keys(prefix) {
+            // every used nonce costs fuel, stop once it runs out
+            self.meter.charge(self.meter.schedule.used_nonce);
+            if self.meter.exhausted() {
+                return self.check_fail("out of fuel");
+            }
+            let replayed = match self.get_current(&used) {
+                Some(Value::Bin { hint: _, data }) => data == nonce,
+                Some(Value::Str { hint: _, data }) => data.as_bytes() == nonce.as_slice(),
+                _ => false,
+            };
+            if replayed {
+                info!("check_fresh_nonce({key}, {prefix}) -> false");
+                return self.check_fail(&format!("nonce already used at {used}"));
+            }
+        }
+
+        info!("check_fresh_nonce({key}, {prefix}) -> true");
+        self.succeed()
+    }
+
+    /// Verifies the fingerprint of the proposed public key matches the fingerprint already
+    /// committed to
+    pub fn check_fingerprint(&mut self, key: &str, fingerprint: &str) -> Val {
+        self.begin_check("check_fingerprint", &[key, fingerprint]);
+        info!("check_fingerprint: loading from current {fingerprint}");
+        // look up the fingerprint and try to decode it
+        let hash = {
+            match self.get_current(fingerprint) {
+                Some(Value::Bin { hint: _, data }) => match Multihash::try_from(data.as_ref()) {
+                    Ok(hash) => hash,
+                    Err(e) => return self.check_fail(&e.to_string()),
+                },
+                Some(_) => return self.check_fail(&format!("unexpected value type associated with {fingerprint}")),
+                None => return self.check_fail(&format!("kvp missing key: {fingerprint}")),
+            }
+        };
+
+        // look up the public key and try to decode it
+        info!("check_fingerprint: loading from proposed {key}");
+        let pubkey = {
+            match self.get_proposed(key) {
+                Some(Value::Bin { hint: _, data }) => match Multikey::try_from(data.as_ref()) {
+                    Ok(mk) => {
+                        // hashing the public key costs fuel
+                        self.meter.charge_hash(data.len());
+                        mk
+                    }
+                    Err(e) => return self.check_fail(&e.to_string()),
+                },
+                Some(_) => return self.check_fail(&format!("unexpected value type associated with {key}")),
+                None => return self.check_fail(&format!("no multikey associated with {key}")),
+            }
+        };
+
+        // calculate the fingerprint of the public key with the same hash codec
+        let fp = match pubkey.fingerprint_view() {
+            Ok(fv) => match fv.fingerprint(hash.codec()) {
+                Ok(fp) => fp,
+                Err(e) => return self.check_fail(&e.to_string()),
+            },
+            Err(e) => return self.check_fail(&e.to_string()),
+        };
+
+        // check that the fingerprints match
+        if hash == fp {
+            info!("check_fingerprint({key}, {fingerprint}) -> true");
+            self.succeed()
+        } else {
+            info!("check_fingerprint({key}, {fingerprint}) -> false");
+            self.check_fail("fingerprint doesn't match")
+        }
+    }
 }
@@ -0,0 +1,127 @@
+// SPDX-License-Identifier: FSL-1.1
+
+/// The fuel cost of each WACC host function. The costs are deducted from the
+/// store's fuel when the engine consumes fuel and are ignored otherwise.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CostSchedule {
+    /// The cost of _branch
+    pub branch: u64,
+    /// The cost of _check_aggregate_signature, not counting the signers
+    pub check_aggregate_signature: u64,
+    /// The additional cost of _check_aggregate_signature per signer
+    pub aggregate_signer: u64,
+    /// The cost of _check_eq
+    pub check_eq: u64,
+    /// The cost of _check_fingerprint
+    pub check_fingerprint: u64,
+    /// The cost of _check_fresh_nonce, not counting the used nonces
+    pub check_fresh_nonce: u64,
+    /// The additional cost of _check_fresh_nonce per used nonce compared
+    pub used_nonce: u64,
+    /// The cost of _check_preimage
+    pub check_preimage: u64,
+    /// The cost of the _check_signature functions
+    pub check_signature: u64,
+    /// The cost of _log
+    pub log: u64,
+    /// The cost of _push
+    pub push: u64,
+    /// The cost per byte of string read from linear memory
+    pub string_byte: u64,
+    /// The cost per byte of data hashed
+    pub hash_byte: u64,
+}
+
+impl Default for CostSchedule {
+    fn default() -> Self {
+        Self {
+            branch: 10,
+            check_aggregate_signature: 20_000,
+            aggregate_signer: 10_000,
+            check_eq: 20,
+            check_fingerprint: 100,
+            check_fresh_nonce: 50,
+            used_nonce: 20,
+            check_preimage: 100,
+            check_signature: 10_000,
+            log: 10,
+            push: 10,
+            string_byte: 1,
+            hash_byte: 2,
+        }
+    }
+}
+
+impl CostSchedule {
+    /// A schedule where every host function is free
+    pub fn free() -> Self {
+        Self {
+            branch: 0,
+            check_aggregate_signature: 0,
+            aggregate_signer: 0,
+            check_eq: 0,
+            check_fingerprint: 0,
+            check_fresh_nonce: 0,
+            used_nonce: 0,
+            check_preimage: 0,
+            check_signature: 0,
+            log: 0,
+            push: 0,
+            string_byte: 0,
+            hash_byte: 0,
+        }
+    }
+}
+
+/// Meters the fuel used by the host functions. Costs that depend on the data
+/// processed are accumulated as they are incurred and deducted from the
+/// store's fuel before the host function returns. Host functions check
+/// [`Meter::exhausted`] before doing more work so that they stop as soon as
+/// the fuel owed exceeds the fuel left.
+#[derive(Clone, Debug, Default)]
+pub struct Meter {
+    /// The cost schedule
+    pub schedule: CostSchedule,
+    /// The fuel owed but not yet deducted
+    owed: u64,
+    /// The fuel left in the store when the host function was last charged,
+    /// None if the engine doesn't consume fuel
+    available: Option<u64>,
+}
+
+impl Meter {
+    /// Create a new meter with the cost schedule
+    pub fn new(schedule: CostSchedule) -> Self {
+        Self { schedule, owed: 0, available: None }
+    }
+
+    /// Returns if the fuel owed exceeds the fuel left in the store
+    pub fn exhausted(&self) -> bool {
+        self.available.is_some_and(|fuel| self.owed > fuel)
+    }
+
+    /// Records the fuel left in the store
+    pub(crate) fn set_available(&mut self, fuel: u64) {
+        self.available = Some(fuel);
+    }
+
+    /// Add to the fuel owed
+    pub fn charge(&mut self, cost: u64) {
+        self.owed = self.owed.saturating_add(cost);
+    }
+
+    /// Add the cost of reading len bytes of string to the fuel owed
+    pub fn charge_string(&mut self, len: usize) {
+        self.charge(self.schedule.string_byte.saturating_mul(len as u64));
+    }
+
+    /// Add the cost of hashing len bytes to the fuel owed
+    pub fn charge_hash(&mut self, len: usize) {
+        self.charge(self.schedule.hash_byte.saturating_mul(len as u64));
+    }
+
+    /// Take the fuel owed
+    pub(crate) fn take(&mut self) -> u64 {
+        std::mem::take(&mut self.owed)
+    }
+}
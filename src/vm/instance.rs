@@ -1,40 +1,259 @@
 // SPDX-License-Identifier: FSL-1.1
-use crate::{vm::Context, Error};
-use wasmtime::{Linker, Module, Store};
+use crate::{
+    api::Mode,
+    error::VmError,
+    storage::{Local, Storage, Threaded},
+    vm::{Context, RunOutcome},
+    Error, Stack,
+};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use wasmtime::{Engine, Linker, Module, Store, Trap};
+
+/// The fuel accounting of an [`Instance`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fuel {
+    /// The fuel the instance was given
+    pub budget: u64,
+    /// The fuel consumed so far
+    pub consumed: u64,
+    /// The fuel remaining
+    pub remaining: u64,
+}
+
+/// The result of running an [`Instance`] to completion
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RunResult {
+    /// The verdict returned by the script
+    pub verdict: bool,
+    /// The fuel accounting after the run, if the instance was built with fuel
+    pub fuel: Option<Fuel>,
+}
+
+/// The interruption state of an [`Instance`], checked by its epoch callback.
+/// Each instance has its own so the epoch ticks of a shared engine don't
+/// count against the deadlines of other instances.
+#[derive(Debug, Default)]
+pub(crate) struct Interrupt {
+    /// Set when the execution is aborted by an [`InterruptHandle`]
+    pub(crate) interrupted: AtomicBool,
+    /// The wall-clock time the running execution must finish by
+    pub(crate) expires: Mutex<Option<Instant>>,
+}
+
+impl Interrupt {
+    /// Checks the interrupted flag and the deadline
+    pub(crate) fn check(&self, deadline: Option<Duration>) -> Result<(), VmError> {
+        if self.interrupted.load(Ordering::Acquire) {
+            return Err(VmError::Cancelled);
+        }
+        let expires = self.expires.lock().ok().and_then(|expires| *expires);
+        match (deadline, expires) {
+            (Some(deadline), Some(expires)) if Instant::now() >= expires => Err(VmError::Timeout(deadline)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Clears the interrupted flag and the deadline once a run is over
+    pub(crate) fn reset(&self) {
+        self.interrupted.store(false, Ordering::Release);
+        if let Ok(mut expires) = self.expires.lock() {
+            *expires = None;
+        }
+    }
+}
+
+/// A handle that aborts the execution of an [`Instance`] from another thread
+#[derive(Clone)]
+pub struct InterruptHandle {
+    engine: Engine,
+    interrupt: Arc<Interrupt>,
+}
+
+impl InterruptHandle {
+    /// Aborts the execution, the run returns [`VmError::Cancelled`]. If the
+    /// instance isn't running it is aborted as soon as its next run starts,
+    /// the runs after that aren't affected.
+    /// This advances the epoch of the engine so the instance notices right
+    /// away, other instances sharing the engine only check their own state.
+    pub fn interrupt(&self) {
+        self.interrupt.interrupted.store(true, Ordering::Release);
+        self.engine.increment_epoch();
+    }
+}
 
 /// Represents an instance of a WACC containing the options, code, as well as
 /// the application state and Wac execution context.
-pub struct Instance<'a>
+pub struct Instance<'a, M: Storage = Local>
 {
     /// Virtual machine linker
-    pub linker: Linker<Context<'a>>,
+    pub linker: Linker<Context<'a, M>>,
 
     /// Virtual machine module to execute
     pub module: Module,
 
     /// Virtual machine store for state
-    pub store: Store<Context<'a>>,
+    pub store: Store<Context<'a, M>>,
+
+    /// The fuel budget, if the engine consumes fuel
+    pub(crate) budget: Option<u64>,
+
+    /// The wall-clock limit for each run
+    pub(crate) deadline: Option<Duration>,
+
+    /// The interruption state, if the engine uses epoch interruption
+    pub(crate) interrupt: Option<Arc<Interrupt>>,
+
+    /// How the WACC functions were registered, which must match how the
+    /// instance is run
+    pub(crate) mode: Mode,
 }
 
-impl<'a> Instance<'a>
+impl<M: Storage> Instance<'_, M>
 {
-    /// Executes the instance to completion
-    pub fn run(&mut self, fname: &str) -> Result<bool, Error> {
+    /// Executes the instance to completion and returns the verdict along
+    /// with the fuel consumed. Instances built with async support must be run
+    /// with [`Instance::run_async`] instead.
+    pub fn run(&mut self, fname: &str) -> Result<RunResult, Error> {
+        if self.mode == Mode::Async {
+            return Err(VmError::AsyncRequired.into());
+        }
+        self.start_deadline()?;
+        let result = self.call(fname);
+        self.finish_deadline();
+        result
+    }
+
+    /// Instantiates the module and calls the function
+    fn call(&mut self, fname: &str) -> Result<RunResult, Error> {
         let instance = self
             .linker
             .instantiate(&mut self.store, &self.module)
-            .map_err(|e| Error::Wasmtime(e.to_string()))?;
+            .map_err(|e| self.trap(e))?;
         let func = instance
             .get_typed_func::<(), i32>(&mut self.store, fname)
             .map_err(|e| Error::Wasmtime(e.to_string()))?;
-        Ok(func
-            .call(&mut self.store, ())
-            .map_err(|e| Error::Wasmtime(e.to_string()))?
-            != 0)
+        let result = func.call(&mut self.store, ());
+        let verdict = result.map_err(|e| self.trap(e))? != 0;
+        Ok(RunResult { verdict, fuel: self.fuel() })
+    }
+
+    /// Executes the instance to completion and returns the outcome
+    pub fn execute(&mut self, fname: &str) -> Result<RunOutcome, Error> {
+        let result = self.run(fname)?;
+        Ok(self.outcome(result.verdict))
+    }
+
+    /// Collects the outcome of the execution from the context
+    fn outcome(&self, verdict: bool) -> RunOutcome {
+        let context = self.store.data();
+        RunOutcome {
+            verdict,
+            checks: context.checks.clone(),
+            pstack_len: context.pstack.len(),
+            rstack_len: context.rstack.len(),
+            fuel: self.fuel(),
+            log: context.log.clone(),
+            access: context.access.clone(),
+        }
+    }
+
+    /// Gets the fuel accounting if the instance was built with fuel
+    pub fn fuel(&self) -> Option<Fuel> {
+        let budget = self.budget?;
+        let remaining = self.store.get_fuel().ok()?;
+        Some(Fuel {
+            budget,
+            consumed: budget.saturating_sub(remaining),
+            remaining,
+        })
+    }
+
+    /// Gets a handle that can abort the execution from another thread. This
+    /// requires a [`crate::vm::Runtime`] with deadlines enabled.
+    pub fn interrupt_handle(&self) -> Result<InterruptHandle, Error> {
+        match &self.interrupt {
+            Some(interrupt) => Ok(InterruptHandle {
+                engine: self.store.engine().clone(),
+                interrupt: interrupt.clone(),
+            }),
+            None => Err(VmError::IncompatibleRuntime("epoch interruption is not enabled".to_string()).into()),
+        }
     }
 
     /// Gets the accumulated log data from the context
     pub fn log(&self) -> Vec<u8> {
         self.store.data().log.clone()
     }
+
+    /// Starts the wall-clock for the deadline of the run
+    fn start_deadline(&self) -> Result<(), Error> {
+        if let (Some(deadline), Some(interrupt)) = (self.deadline, &self.interrupt) {
+            let mut expires = interrupt.expires.lock().map_err(|e| Error::custom(&e))?;
+            *expires = Some(Instant::now() + deadline);
+        }
+        Ok(())
+    }
+
+    /// Clears the interruption state so it doesn't carry over to the next run
+    fn finish_deadline(&self) {
+        if let Some(interrupt) = &self.interrupt {
+            interrupt.reset();
+        }
+    }
+
+    /// Maps wasmtime traps to typed errors
+    fn trap(&self, e: wasmtime::Error) -> Error {
+        // deadlines and cancellation are reported by the epoch callback
+        let e = match e.downcast::<VmError>() {
+            Ok(e) => return e.into(),
+            Err(e) => e,
+        };
+        match e.downcast_ref::<Trap>() {
+            Some(Trap::OutOfFuel) => VmError::OutOfFuel(self.budget.unwrap_or_default()).into(),
+            _ => Error::Wasmtime(e.to_string()),
+        }
+    }
+}
+
+impl Instance<'_, Threaded>
+{
+    /// Executes the instance to completion on an engine with async support,
+    /// awaiting the async storage lookups of the WACC functions
+    pub async fn run_async(&mut self, fname: &str) -> Result<RunResult, Error> {
+        if self.mode == Mode::Sync {
+            return Err(VmError::AsyncUnsupported.into());
+        }
+        self.start_deadline()?;
+        let result = self.call_async(fname).await;
+        self.finish_deadline();
+        result
+    }
+
+    /// Instantiates the module and calls the function asynchronously
+    async fn call_async(&mut self, fname: &str) -> Result<RunResult, Error> {
+        let instance = match self.linker.instantiate_async(&mut self.store, &self.module).await {
+            Ok(instance) => instance,
+            Err(e) => return Err(self.trap(e)),
+        };
+        let func = instance
+            .get_typed_func::<(), i32>(&mut self.store, fname)
+            .map_err(|e| Error::Wasmtime(e.to_string()))?;
+        match func.call_async(&mut self.store, ()).await {
+            Ok(result) => Ok(RunResult { verdict: result != 0, fuel: self.fuel() }),
+            Err(e) => Err(self.trap(e)),
+        }
+    }
+
+    /// Executes the instance to completion on an engine with async support
+    /// and returns the outcome
+    pub async fn execute_async(&mut self, fname: &str) -> Result<RunOutcome, Error> {
+        let result = self.run_async(fname).await?;
+        Ok(self.outcome(result.verdict))
+    }
 }
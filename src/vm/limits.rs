@@ -0,0 +1,134 @@
+// SPDX-License-Identifier: FSL-1.1
+use crate::{error::VmError, vm::text, Error};
+use wasmparser::{Parser, Payload, TypeRef};
+
+/// Static limits on the resources a script declares, checked before the
+/// script is compiled so that pathological modules are rejected cheaply
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Limits {
+    /// The maximum size of the module in bytes
+    pub module_size: usize,
+    /// The maximum number of functions, imported and defined
+    pub functions: u64,
+    /// The maximum number of data segments
+    pub data_segments: u64,
+    /// The maximum initial and declared maximum memory pages
+    pub memory_pages: u64,
+    /// The maximum initial and declared maximum table size
+    pub table_size: u64,
+    /// The maximum number of locals in a function
+    pub locals: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            module_size: 1 << 20, /* 1MB */
+            functions: 1_000,
+            data_segments: 1_000,
+            memory_pages: 64, /* 4MB */
+            table_size: 1_000,
+            locals: 1_000,
+        }
+    }
+}
+
+impl Limits {
+    /// No limits
+    pub fn unlimited() -> Self {
+        Self {
+            module_size: usize::MAX,
+            functions: u64::MAX,
+            data_segments: u64::MAX,
+            memory_pages: u64::MAX,
+            table_size: u64::MAX,
+            locals: u64::MAX,
+        }
+    }
+
+    /// Checks the size of the script, in binary or text format, without
+    /// parsing it. This is done before a text script is converted.
+    pub(crate) fn check_size(&self, bytes: &[u8]) -> Result<(), Error> {
+        match bytes.len() > self.module_size {
+            true => Err(VmError::LimitExceeded {
+                limit: "module size".to_string(),
+                value: bytes.len() as u64,
+                max: self.module_size as u64,
+            }
+            .into()),
+            false => Ok(()),
+        }
+    }
+
+    /// Checks the script, in binary or text format, against the limits
+    pub fn check(&self, bytes: &[u8]) -> Result<(), Error> {
+        let exceeded = |limit: &str, value: u64, max: u64| -> Result<(), Error> {
+            match value > max {
+                true => Err(VmError::LimitExceeded {
+                    limit: limit.to_string(),
+                    value,
+                    max,
+                }
+                .into()),
+                false => Ok(()),
+            }
+        };
+        let parse = |e: wasmparser::BinaryReaderError| Error::Wasmtime(e.to_string());
+
+        // reject large modules before parsing anything
+        self.check_size(bytes)?;
+        let wasm = text::to_wasm(bytes)?;
+        self.check_size(&wasm)?;
+
+        let mut functions = 0u64;
+        for payload in Parser::new(0).parse_all(&wasm) {
+            match payload.map_err(parse)? {
+                Payload::ImportSection(reader) => {
+                    for import in reader {
+                        match import.map_err(parse)?.ty {
+                            TypeRef::Func(_) => functions += 1,
+                            TypeRef::Memory(m) => {
+                                exceeded("memory pages", m.initial, self.memory_pages)?;
+                                exceeded("memory pages", m.maximum.unwrap_or_default(), self.memory_pages)?;
+                            }
+                            TypeRef::Table(t) => {
+                                exceeded("table size", u64::from(t.initial), self.table_size)?;
+                                exceeded("table size", u64::from(t.maximum.unwrap_or_default()), self.table_size)?;
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                Payload::FunctionSection(reader) => {
+                    functions += u64::from(reader.count());
+                }
+                Payload::TableSection(reader) => {
+                    for table in reader {
+                        let ty = table.map_err(parse)?.ty;
+                        exceeded("table size", u64::from(ty.initial), self.table_size)?;
+                        exceeded("table size", u64::from(ty.maximum.unwrap_or_default()), self.table_size)?;
+                    }
+                }
+                Payload::MemorySection(reader) => {
+                    for memory in reader {
+                        let m = memory.map_err(parse)?;
+                        exceeded("memory pages", m.initial, self.memory_pages)?;
+                        exceeded("memory pages", m.maximum.unwrap_or_default(), self.memory_pages)?;
+                    }
+                }
+                Payload::DataSection(reader) => {
+                    exceeded("data segments", u64::from(reader.count()), self.data_segments)?;
+                }
+                Payload::CodeSectionEntry(body) => {
+                    let mut locals = 0u64;
+                    for local in body.get_locals_reader().map_err(parse)? {
+                        locals = locals.saturating_add(u64::from(local.map_err(parse)?.0));
+                    }
+                    exceeded("locals", locals, self.locals)?;
+                }
+                _ => {}
+            }
+        }
+        exceeded("functions", functions, self.functions)
+    }
+}
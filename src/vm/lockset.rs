@@ -0,0 +1,90 @@
+// SPDX-License-Identifier: FSL-1.1
+use crate::vm::{Check, RunOutcome};
+
+/// A lock script in a precedence ordered set of lock scripts
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lock {
+    /// The lock script
+    pub bytes: Vec<u8>,
+    /// The entry point of the lock script
+    pub func: String,
+    /// The branch context key-path, the verifier context is used if None
+    pub context: Option<String>,
+}
+
+impl Lock {
+    /// Create a new lock from the script and its entry point
+    pub fn new(bytes: impl AsRef<[u8]>, func: &str) -> Self {
+        Self {
+            bytes: bytes.as_ref().to_vec(),
+            func: func.to_string(),
+            context: None,
+        }
+    }
+
+    /// Set the branch context key-path the lock script runs in
+    pub fn with_context(mut self, context: &str) -> Self {
+        self.context = Some(context.to_string());
+        self
+    }
+}
+
+/// The lock script that satisfied the update
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Satisfied {
+    /// The index of the lock script in the lock set
+    pub index: usize,
+    /// The check that satisfied the lock script, None if the lock script
+    /// succeeded without running a check
+    pub check: Option<Check>,
+}
+
+/// The outcome of a lock script in a lock set
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LockOutcome {
+    /// The lock script ran to completion
+    Ran(RunOutcome),
+    /// The lock script couldn't be built or aborted, it isn't satisfied
+    Failed(String),
+}
+
+impl LockOutcome {
+    /// Returns true if the lock script ran and returned true
+    pub fn verdict(&self) -> bool {
+        matches!(self, Self::Ran(outcome) if outcome.verdict)
+    }
+
+    /// Returns the outcome of the run, None if the lock script failed
+    pub fn outcome(&self) -> Option<&RunOutcome> {
+        match self {
+            Self::Ran(outcome) => Some(outcome),
+            Self::Failed(_) => None,
+        }
+    }
+
+    /// Returns the reason the lock script failed, None if it ran
+    pub fn error(&self) -> Option<&str> {
+        match self {
+            Self::Ran(_) => None,
+            Self::Failed(reason) => Some(reason),
+        }
+    }
+}
+
+/// The result of verifying an unlock script against a set of lock scripts
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LockSetVerification {
+    /// The outcome of the unlock script
+    pub unlock: RunOutcome,
+    /// The outcomes of the lock scripts that were run, in order of precedence
+    pub locks: Vec<LockOutcome>,
+    /// The first lock script that was satisfied
+    pub satisfied: Option<Satisfied>,
+}
+
+impl LockSetVerification {
+    /// Returns true if one of the lock scripts was satisfied
+    pub fn verdict(&self) -> bool {
+        self.satisfied.is_some()
+    }
+}
@@ -0,0 +1,50 @@
+// SPDX-License-Identifier: FSL-1.1
+use crate::Error;
+use multicodec::Codec;
+use multihash::mh;
+use multitrait::EncodeInto;
+
+/// Selects how the message verified by a signature check is constructed from
+/// the value stored in the proposed state
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum SignedMessage {
+    /// The signature is over the raw value bytes
+    #[default]
+    Raw,
+    /// The signature is over the encoded Multihash of the value bytes using
+    /// the given hash codec
+    Hashed(Codec),
+    /// The signature is over the value bytes prefixed with a domain
+    /// separation tag made from the context and the message key-path
+    Domain,
+}
+
+impl SignedMessage {
+    /// Builds the domain separation tag for the given context and key-path
+    pub fn domain_tag(context: &str, key: &str) -> String {
+        format!("{context}{key}")
+    }
+
+    /// Constructs the message bytes that are signed for the value stored
+    /// under the key-path in the given context
+    pub fn encode(&self, context: &str, key: &str, data: &[u8]) -> Result<Vec<u8>, Error> {
+        match self {
+            SignedMessage::Raw => Ok(data.to_vec()),
+            SignedMessage::Hashed(codec) => {
+                let hash = mh::Builder::new_from_bytes(*codec, data)
+                    .map_err(|e| Error::custom(&e))?
+                    .try_build()
+                    .map_err(|e| Error::custom(&e))?;
+                Ok(hash.into())
+            }
+            SignedMessage::Domain => {
+                // varuint tag length || tag || value
+                let tag = Self::domain_tag(context, key);
+                let mut msg = tag.len().encode_into();
+                msg.extend_from_slice(tag.as_bytes());
+                msg.extend_from_slice(data);
+                Ok(msg)
+            }
+        }
+    }
+}
@@ -0,0 +1,36 @@
+// SPDX-License-Identifier: FSL-1.1
+use crate::vm::{
+    context::{AccessSet, Check},
+    Fuel,
+};
+
+/// The outcome of executing an [`crate::vm::Instance`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RunOutcome {
+    /// The value returned by the script
+    pub verdict: bool,
+    /// The check_* operations executed, in order
+    pub checks: Vec<Check>,
+    /// The number of values left on the parameter stack
+    pub pstack_len: usize,
+    /// The number of values left on the return stack
+    pub rstack_len: usize,
+    /// The fuel accounting, if the instance was built with fuel
+    pub fuel: Option<Fuel>,
+    /// The log output of the script
+    pub log: Vec<u8>,
+    /// The keys and prefixes the script read, and the branches it expanded
+    pub access: AccessSet,
+}
+
+impl RunOutcome {
+    /// Returns the checks that failed
+    pub fn failures(&self) -> impl Iterator<Item = &Check> {
+        self.checks.iter().filter(|c| !c.passed)
+    }
+
+    /// Returns the log output as a string
+    pub fn log_string(&self) -> String {
+        String::from_utf8_lossy(&self.log).to_string()
+    }
+}
@@ -0,0 +1,37 @@
+// SPDX-License-Identifier: FSL-1.1
+use std::fmt;
+
+/// The execution phase of a script, which determines the WACC functions it
+/// can import
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Phase {
+    /// Every WACC function is available
+    #[default]
+    Any,
+    /// Unlock scripts stage data: push, branch and log
+    Unlock,
+    /// Lock scripts check data: the check_* functions, branch and log
+    Lock,
+}
+
+impl Phase {
+    /// Returns if the data staging functions are available
+    pub fn stages(&self) -> bool {
+        matches!(self, Phase::Any | Phase::Unlock)
+    }
+
+    /// Returns if the check_* functions are available
+    pub fn checks(&self) -> bool {
+        matches!(self, Phase::Any | Phase::Lock)
+    }
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Phase::Any => write!(f, "any"),
+            Phase::Unlock => write!(f, "unlock"),
+            Phase::Lock => write!(f, "lock"),
+        }
+    }
+}
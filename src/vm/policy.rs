@@ -0,0 +1,102 @@
+// SPDX-License-Identifier: FSL-1.1
+use crate::error::VmError;
+use multicodec::Codec;
+use multikey::Multikey;
+use multisig::Multisig;
+use multiutil::CodecInfo;
+
+/// Returns the approximate security level, in bits, of the given public key
+/// codec. Unknown codecs have a security level of zero.
+pub fn security_level(codec: Codec) -> u32 {
+    match codec {
+        Codec::Ed25519Pub => 128,
+        Codec::Secp256K1Pub => 128,
+        Codec::P256Pub => 128,
+        Codec::P384Pub => 192,
+        Codec::P521Pub => 256,
+        Codec::Bls12381G1Pub => 128,
+        Codec::Bls12381G2Pub => 128,
+        _ => 0,
+    }
+}
+
+/// The signature algorithm policy enforced by `check_signature` before any
+/// signature is verified. The default policy allows everything.
+#[derive(Clone, Debug, Default)]
+pub struct SignaturePolicy {
+    /// The allowed public key codecs, empty allows all codecs
+    allowed_keys: Vec<Codec>,
+    /// The denied public key codecs
+    denied_keys: Vec<Codec>,
+    /// The allowed multisig codecs, empty allows all codecs
+    allowed_sigs: Vec<Codec>,
+    /// The denied multisig codecs
+    denied_sigs: Vec<Codec>,
+    /// The minimum security level in bits of the public key
+    min_security_level: Option<u32>,
+}
+
+impl SignaturePolicy {
+    /// create a new policy that allows everything
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a public key codec to the allow list
+    pub fn allow_key(mut self, codec: Codec) -> Self {
+        self.allowed_keys.push(codec);
+        self
+    }
+
+    /// Add a public key codec to the deny list
+    pub fn deny_key(mut self, codec: Codec) -> Self {
+        self.denied_keys.push(codec);
+        self
+    }
+
+    /// Add a multisig codec to the allow list
+    pub fn allow_sig(mut self, codec: Codec) -> Self {
+        self.allowed_sigs.push(codec);
+        self
+    }
+
+    /// Add a multisig codec to the deny list
+    pub fn deny_sig(mut self, codec: Codec) -> Self {
+        self.denied_sigs.push(codec);
+        self
+    }
+
+    /// Require public keys to have at least the given security level in bits
+    pub fn with_min_security_level(mut self, bits: u32) -> Self {
+        self.min_security_level = Some(bits);
+        self
+    }
+
+    /// Checks the public key and signature against the policy
+    pub fn check(&self, pubkey: &Multikey, sig: &Multisig) -> Result<(), VmError> {
+        let key_codec = pubkey.codec();
+        if self.denied_keys.contains(&key_codec)
+            || (!self.allowed_keys.is_empty() && !self.allowed_keys.contains(&key_codec))
+        {
+            return Err(VmError::SignaturePolicy(format!("key codec {key_codec:?} not allowed")));
+        }
+
+        let sig_codec = sig.codec();
+        if self.denied_sigs.contains(&sig_codec)
+            || (!self.allowed_sigs.is_empty() && !self.allowed_sigs.contains(&sig_codec))
+        {
+            return Err(VmError::SignaturePolicy(format!("signature codec {sig_codec:?} not allowed")));
+        }
+
+        if let Some(min) = self.min_security_level {
+            let level = security_level(key_codec);
+            if level < min {
+                return Err(VmError::SignaturePolicy(
+                    format!("key codec {key_codec:?} security level {level} is below {min}")
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+// SPDX-License-Identifier: FSL-1.1
+use crate::{error::VmError, vm::text, Error};
+use wasmparser::{Validator, WasmFeatures};
+use wasmtime::Config;
+
+/// The execution profile used to configure the engine. The same profile must
+/// be used by the [`crate::vm::Compiler`] and the [`crate::vm::Builder`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Profile {
+    /// The wasmtime defaults
+    #[default]
+    Default,
+    /// Deterministic execution so that every node reaches the same verdict
+    /// for the same script: no threads, no relaxed SIMD and canonical NaNs
+    Deterministic {
+        /// Reject modules that use floating point types or instructions
+        reject_floats: bool,
+    },
+}
+
+impl Profile {
+    /// Returns if modules using floating point are rejected
+    pub fn rejects_floats(&self) -> bool {
+        matches!(self, Profile::Deterministic { reject_floats: true })
+    }
+
+    /// Applies the profile to the engine configuration
+    pub(crate) fn configure(&self, config: &mut Config) {
+        if let Profile::Deterministic { .. } = self {
+            config.wasm_threads(false);
+            config.wasm_relaxed_simd(false);
+            config.relaxed_simd_deterministic(true);
+            config.cranelift_nan_canonicalization(true);
+        }
+    }
+
+    /// Validates the script against the profile before it is compiled
+    pub fn validate(&self, bytes: &[u8]) -> Result<(), Error> {
+        if self.rejects_floats() {
+            let wasm = text::to_wasm(bytes)?;
+            reject_floats(&wasm)?;
+        }
+        Ok(())
+    }
+}
+
+/// Fails if the module uses floating point types or instructions anywhere,
+/// including conversions and the SIMD float lanes
+fn reject_floats(wasm: &[u8]) -> Result<(), Error> {
+    let no_floats = WasmFeatures { floats: false, ..WasmFeatures::default() };
+    match Validator::new_with_features(no_floats).validate_all(wasm) {
+        Ok(_) => Ok(()),
+        // only blame floating point if the module is otherwise valid
+        Err(e) => match Validator::new().validate_all(wasm) {
+            Ok(_) => Err(VmError::NonDeterministic(format!("floating point: {}", e.message())).into()),
+            Err(e) => Err(Error::Wasmtime(e.to_string())),
+        },
+    }
+}
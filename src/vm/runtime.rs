@@ -0,0 +1,341 @@
+// SPDX-License-Identifier: FSL-1.1
+use crate::{vm::{artifact, text, DiskCache, Limits, Profile}, Error};
+use log::warn;
+use lru::LruCache;
+use multicodec::Codec;
+use multihash::{mh, Multihash};
+use std::{
+    num::NonZeroUsize,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+use wasmtime::{Config, Engine, Module};
+
+/// The default number of compiled modules kept in the cache
+pub const DEFAULT_CACHE_SIZE: usize = 64;
+
+/// The default interval between epoch ticks when deadlines are enabled
+pub const DEFAULT_EPOCH_TICK: Duration = Duration::from_millis(10);
+
+/// The options that the compiled code depends on. Artifacts compiled with
+/// one set of options can only be loaded by an engine with the same options.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EngineOptions {
+    /// The engine consumes fuel
+    pub fuel: bool,
+    /// The execution profile
+    pub profile: Profile,
+    /// The engine uses epoch interruption to enforce deadlines
+    pub epochs: bool,
+    /// The engine supports async execution
+    pub async_support: bool,
+}
+
+impl EngineOptions {
+    /// Returns the engine configuration used for executing WACC scripts
+    pub(crate) fn config(&self) -> Config {
+        let mut config = Config::default();
+        config.consume_fuel(self.fuel);
+        config.epoch_interruption(self.epochs);
+        config.async_support(self.async_support);
+        self.profile.configure(&mut config);
+        config
+    }
+
+    /// Encodes the options that aren't covered by the engine fingerprint
+    pub(crate) fn flags(&self) -> u8 {
+        (self.fuel as u8) | ((self.profile.rejects_floats() as u8) << 1)
+    }
+}
+
+/// Calculates the Multihash of a script used to identify its compiled module
+pub fn script_hash(bytes: &[u8]) -> Result<Multihash, Error> {
+    mh::Builder::new_from_bytes(Codec::Sha2256, bytes)
+        .map_err(|e| Error::custom(&e))?
+        .try_build()
+        .map_err(|e| Error::custom(&e))
+}
+
+/// The key of a compiled module in the cache, the script Multihash and the
+/// limits it was checked against, none for precompiled artifacts
+type ModuleKey = (Vec<u8>, Option<Limits>);
+
+/// A reusable runtime that owns a single wasmtime [`Engine`] and a least
+/// recently used cache of compiled modules keyed by the script Multihash.
+/// A [`crate::vm::Builder`] can borrow it to avoid recompiling the same
+/// script for every instance. Optionally the compiled modules are also kept
+/// in a [`DiskCache`] so they survive restarts.
+pub struct Runtime {
+    engine: Engine,
+    options: EngineOptions,
+    limits: Limits,
+    modules: Mutex<LruCache<ModuleKey, Module>>,
+    disk: Option<DiskCache>,
+    ticker: Option<Ticker>,
+}
+
+/// A background thread that increments the engine epoch at a fixed interval
+struct Ticker {
+    tick: Duration,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Ticker {
+    fn start(engine: Engine, tick: Duration) -> Result<Self, Error> {
+        let stop = Arc::new(AtomicBool::new(false));
+        let flag = stop.clone();
+        let handle = thread::Builder::new()
+            .name("wacc-epoch".to_string())
+            .spawn(move || {
+                while !flag.load(Ordering::Relaxed) {
+                    thread::sleep(tick);
+                    engine.increment_epoch();
+                }
+            })
+            .map_err(|e| Error::custom(&e))?;
+        Ok(Self {
+            tick,
+            stop,
+            handle: Some(handle),
+        })
+    }
+}
+
+impl Drop for Ticker {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Runtime {
+    /// create a new runtime builder
+    pub fn builder() -> RuntimeBuilder {
+        RuntimeBuilder::default()
+    }
+
+    /// Get the engine shared by all instances built with this runtime
+    pub fn engine(&self) -> &Engine {
+        &self.engine
+    }
+
+    /// Returns if the engine was configured to consume fuel
+    pub fn fuel(&self) -> bool {
+        self.options.fuel
+    }
+
+    /// Get the options the engine was configured with
+    pub fn options(&self) -> EngineOptions {
+        self.options
+    }
+
+    /// Get the interval between epoch ticks if deadlines are enabled
+    pub fn epoch_tick(&self) -> Option<Duration> {
+        self.ticker.as_ref().map(|t| t.tick)
+    }
+
+    /// Get the static limits scripts are checked against before compiling
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    /// Returns the number of compiled modules in the cache
+    pub fn cached(&self) -> usize {
+        self.modules.lock().map(|m| m.len()).unwrap_or_default()
+    }
+
+    /// Get the compiled module for the script, compiling and caching it if
+    /// it isn't already in the cache
+    pub fn module(&self, bytes: &[u8]) -> Result<Module, Error> {
+        self.checked_module(bytes, self.limits)
+    }
+
+    /// Get the compiled module for the script, checking it against the limits
+    /// and compiling it if it isn't already in the cache with the same limits
+    pub(crate) fn checked_module(&self, bytes: &[u8], limits: Limits) -> Result<Module, Error> {
+        let script = script_hash(bytes)?;
+        let key = (script.clone().into(), Some(limits));
+
+        // check the cache first
+        if let Some(module) = self.lock()?.get(&key) {
+            return Ok(module.clone());
+        }
+
+        // validate and compile without holding the lock so other threads aren't blocked
+        limits.check_size(bytes)?;
+        let wasm = text::to_wasm(bytes)?;
+        limits.check(&wasm)?;
+        self.options.profile.validate(&wasm)?;
+        let module = match &self.disk {
+            Some(disk) => self.load_or_compile(disk, &script, &wasm)?,
+            None => Module::from_binary(&self.engine, &wasm).map_err(|e| Error::Wasmtime(e.to_string()))?,
+        };
+        self.lock()?.put(key, module.clone());
+        Ok(module)
+    }
+
+    /// Loads the module from the disk cache or compiles the script and stores
+    /// the artifact in the disk cache
+    fn load_or_compile(&self, disk: &DiskCache, script: &Multihash, bytes: &[u8]) -> Result<Module, Error> {
+        // SAFETY: the disk cache key is secret, see RuntimeBuilder::with_disk_cache
+        match unsafe { disk.get(script, &self.engine, &self.options) } {
+            Ok(Some(module)) => return Ok(module),
+            Ok(None) => {}
+            Err(e) => {
+                warn!("discarding cached module: {e}");
+                disk.remove(script, &self.engine, &self.options)?;
+            }
+        }
+
+        let aot = self.engine.precompile_module(bytes).map_err(|e| Error::Wasmtime(e.to_string()))?;
+        let data = artifact::encode(&self.engine, &self.options, &aot);
+        disk.put(script, &self.engine, &self.options, &data)?;
+        // SAFETY: the artifact was just compiled by this engine
+        unsafe { artifact::deserialize(&self.engine, &self.options, &data) }
+    }
+
+    /// Get the module for the precompiled artifact produced by a
+    /// [`crate::vm::Compiler`], checking and caching it if it isn't already
+    /// in the cache
+    ///
+    /// # Safety
+    ///
+    /// The artifact is deserialized into executable machine code without
+    /// being validated. It must come from a trusted [`crate::vm::Compiler`]
+    /// and not have been modified since.
+    pub unsafe fn precompiled(&self, bytes: &[u8]) -> Result<Module, Error> {
+        let key = (script_hash(bytes)?.into(), None);
+
+        // check the cache first
+        if let Some(module) = self.lock()?.get(&key) {
+            return Ok(module.clone());
+        }
+
+        // SAFETY: the caller guarantees the artifact is trusted
+        let module = unsafe { artifact::deserialize(&self.engine, &self.options, bytes)? };
+        self.lock()?.put(key, module.clone());
+        Ok(module)
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, LruCache<ModuleKey, Module>>, Error> {
+        self.modules.lock().map_err(|e| Error::custom(&e))
+    }
+}
+
+/// Builder type for constructing a [`Runtime`]
+pub struct RuntimeBuilder {
+    options: EngineOptions,
+    limits: Limits,
+    cache_size: usize,
+    cache_dir: Option<(PathBuf, Vec<u8>)>,
+    epoch_tick: Duration,
+}
+
+impl Default for RuntimeBuilder {
+    fn default() -> Self {
+        Self {
+            options: EngineOptions::default(),
+            limits: Limits::default(),
+            cache_size: DEFAULT_CACHE_SIZE,
+            cache_dir: None,
+            epoch_tick: DEFAULT_EPOCH_TICK,
+        }
+    }
+}
+
+impl RuntimeBuilder {
+    /// Enables the use of fuel by instances built with the runtime
+    pub fn with_fuel(mut self) -> Self {
+        self.options.fuel = true;
+        self
+    }
+
+    /// Sets the execution profile of the engine
+    pub fn with_profile(mut self, profile: Profile) -> Self {
+        self.options.profile = profile;
+        self
+    }
+
+    /// Enables async execution of instances built with the runtime, see
+    /// [`crate::vm::Instance::run_async`]
+    pub fn with_async(mut self) -> Self {
+        self.options.async_support = true;
+        self
+    }
+
+    /// Enables deadlines for instances built with the runtime. The runtime
+    /// owns a background thread that advances the engine epoch every tick.
+    pub fn with_deadlines(mut self) -> Self {
+        self.options.epochs = true;
+        self
+    }
+
+    /// Enables deadlines and sets the interval between epoch ticks, which is
+    /// the granularity of the deadlines
+    pub fn with_epoch_tick(mut self, tick: Duration) -> Self {
+        self.options.epochs = true;
+        self.epoch_tick = tick;
+        self
+    }
+
+    /// Sets the static limits scripts are checked against before compiling,
+    /// unless the [`crate::vm::Builder`] sets its own
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Sets the maximum number of compiled modules kept in the cache
+    pub fn with_cache_size(mut self, size: usize) -> Self {
+        self.cache_size = size;
+        self
+    }
+
+    /// Keeps compiled modules in the cache directory so they persist across
+    /// restarts. The entries are authenticated with the key, see
+    /// [`DiskCache`].
+    ///
+    /// # Safety
+    ///
+    /// Cached modules are deserialized into executable machine code once they
+    /// are authenticated. The key must be kept secret and must not be stored
+    /// in the cache directory.
+    pub unsafe fn with_disk_cache(mut self, dir: impl Into<PathBuf>, key: impl AsRef<[u8]>) -> Self {
+        self.cache_dir = Some((dir.into(), key.as_ref().to_vec()));
+        self
+    }
+
+    /// Tries to build the [`Runtime`] from the builder configuration
+    pub fn try_build(self) -> Result<Runtime, Error> {
+        let engine = Engine::new(&self.options.config()).map_err(|e| Error::Wasmtime(e.to_string()))?;
+        let cache_size = NonZeroUsize::new(self.cache_size)
+            .ok_or_else(|| Error::custom(&"module cache size must be non-zero"))?;
+        let disk = match self.cache_dir {
+            Some((dir, key)) => Some(DiskCache::new(dir, key)?),
+            None => None,
+        };
+        if self.epoch_tick.is_zero() {
+            return Err(Error::custom(&"epoch tick must be non-zero"));
+        }
+        let ticker = match self.options.epochs {
+            true => Some(Ticker::start(engine.clone(), self.epoch_tick)?),
+            false => None,
+        };
+        Ok(Runtime {
+            engine,
+            options: self.options,
+            limits: self.limits,
+            modules: Mutex::new(LruCache::new(cache_size)),
+            disk,
+            ticker,
+        })
+    }
+}
@@ -0,0 +1,42 @@
+// SPDX-License-Identifier: FSL-1.1
+use crate::{error::VmError, Error};
+use std::borrow::Cow;
+
+/// The magic number at the start of every script in the binary format
+const WASM_MAGIC: &[u8] = b"\0asm";
+
+/// Returns if the script is in the binary format
+pub(crate) fn is_binary(bytes: &[u8]) -> bool {
+    bytes.starts_with(WASM_MAGIC)
+}
+
+/// Checks that the script is in the binary format
+pub(crate) fn binary(bytes: &[u8]) -> Result<Cow<'_, [u8]>, Error> {
+    match is_binary(bytes) {
+        true => Ok(Cow::Borrowed(bytes)),
+        false => Err(VmError::NotBinary.into()),
+    }
+}
+
+/// Converts the script in the text format to the binary format, failing with
+/// the location of the first error
+#[cfg(feature = "wat")]
+pub(crate) fn parse(bytes: &[u8]) -> Result<Cow<'_, [u8]>, Error> {
+    let text = std::str::from_utf8(bytes).map_err(|e| VmError::InvalidText(e.to_string()))?;
+    let wasm = wat::parse_str(text).map_err(|e| VmError::InvalidText(e.to_string()))?;
+    Ok(Cow::Owned(wasm))
+}
+
+/// Text scripts are rejected without the wat feature
+#[cfg(not(feature = "wat"))]
+pub(crate) fn parse(_bytes: &[u8]) -> Result<Cow<'_, [u8]>, Error> {
+    Err(VmError::TextUnsupported.into())
+}
+
+/// Gets the script, in binary or text format, in the binary format
+pub(crate) fn to_wasm(bytes: &[u8]) -> Result<Cow<'_, [u8]>, Error> {
+    match is_binary(bytes) {
+        true => Ok(Cow::Borrowed(bytes)),
+        false => parse(bytes),
+    }
+}
@@ -0,0 +1,207 @@
+// SPDX-License-Identifier: FSL-1.1
+use crate::{api, vm::text};
+use std::collections::BTreeMap;
+use wasmparser::{CompositeType, ExternalKind, FuncType, Parser, Payload, TypeRef, ValType};
+
+/// The name of the module WACC functions are imported from
+pub const WACC_MODULE: &str = "wacc";
+
+/// The name of the memory export WACC functions read from
+pub const MEMORY_EXPORT: &str = "memory";
+
+/// A problem found by the [`Validator`]
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
+pub enum Diagnostic {
+    /// The script isn't a valid WebAssembly module
+    #[error("invalid module: {0}")]
+    InvalidModule(String),
+    /// The script imports from a module other than wacc
+    #[error("import {module}::{name} is not from the wacc module")]
+    ForeignImport {
+        /// The module name of the import
+        module: String,
+        /// The name of the import
+        name: String,
+    },
+    /// The script imports something from wacc that isn't a function
+    #[error("import wacc::{0} is not a function")]
+    NonFunctionImport(String),
+    /// The script imports a function that doesn't exist
+    #[error("unknown WACC function {0}")]
+    UnknownFunction(String),
+    /// The script imports a function with the wrong signature
+    #[error("{name} is declared as {found} but has the signature {expected}")]
+    SignatureMismatch {
+        /// The name of the function
+        name: String,
+        /// The signature of the WACC function
+        expected: String,
+        /// The signature the script declares
+        found: String,
+    },
+    /// The script doesn't export its memory
+    #[error("memory is not exported")]
+    MissingMemoryExport,
+    /// The entry point isn't exported
+    #[error("entry point {0} is not exported")]
+    MissingEntryPoint(String),
+    /// The entry point doesn't have the signature () -> (i32)
+    #[error("entry point {name} has the signature {found} instead of () -> (i32)")]
+    EntryPointSignature {
+        /// The name of the entry point
+        name: String,
+        /// The signature of the entry point
+        found: String,
+    },
+}
+
+fn signature(params: &[ValType], results: &[ValType]) -> String {
+    let list = |types: &[ValType]| {
+        types
+            .iter()
+            .map(|t| format!("{t:?}").to_lowercase())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    format!("({}) -> ({})", list(params), list(results))
+}
+
+/// Statically checks WACC scripts before they are compiled: every import
+/// must be a WACC function with the matching signature, the memory must be
+/// exported and the entry points must exist with the signature () -> (i32).
+#[derive(Clone, Debug, Default)]
+pub struct Validator {
+    entry_points: Vec<String>,
+}
+
+impl Validator {
+    /// create a new validator
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requires the script to export the entry point
+    pub fn with_entry_point(mut self, name: &str) -> Self {
+        self.entry_points.push(name.to_string());
+        self
+    }
+
+    /// Validates the script, in binary or text format, returning every
+    /// problem found
+    pub fn validate(&self, bytes: &[u8]) -> Vec<Diagnostic> {
+        let wasm = match text::to_wasm(bytes) {
+            Ok(wasm) => wasm,
+            Err(e) => return vec![Diagnostic::InvalidModule(e.to_string())],
+        };
+        if let Err(e) = wasmparser::Validator::new().validate_all(&wasm) {
+            return vec![Diagnostic::InvalidModule(e.to_string())];
+        }
+        match self.check(&wasm) {
+            Ok(diagnostics) => diagnostics,
+            Err(e) => vec![Diagnostic::InvalidModule(e.to_string())],
+        }
+    }
+
+    fn check(&self, wasm: &[u8]) -> Result<Vec<Diagnostic>, wasmparser::BinaryReaderError> {
+        let mut diagnostics = Vec::default();
+        let mut types: Vec<Option<FuncType>> = Vec::default();
+        let mut functions: Vec<u32> = Vec::default();
+        let mut exports: BTreeMap<String, (ExternalKind, u32)> = BTreeMap::default();
+
+        for payload in Parser::new(0).parse_all(wasm) {
+            match payload? {
+                Payload::Version { .. } => {}
+                Payload::TypeSection(reader) => {
+                    for group in reader {
+                        for ty in group?.into_types() {
+                            types.push(match ty.composite_type {
+                                CompositeType::Func(f) => Some(f),
+                                _ => None,
+                            });
+                        }
+                    }
+                }
+                Payload::ImportSection(reader) => {
+                    for import in reader {
+                        let import = import?;
+                        if let TypeRef::Func(idx) = import.ty {
+                            functions.push(idx);
+                        }
+                        if import.module != WACC_MODULE {
+                            diagnostics.push(Diagnostic::ForeignImport {
+                                module: import.module.to_string(),
+                                name: import.name.to_string(),
+                            });
+                            continue;
+                        }
+                        let idx = match import.ty {
+                            TypeRef::Func(idx) => idx,
+                            _ => {
+                                diagnostics.push(Diagnostic::NonFunctionImport(import.name.to_string()));
+                                continue;
+                            }
+                        };
+                        let (params, results) = match api::signature(import.name) {
+                            Some(sig) => sig,
+                            None => {
+                                diagnostics.push(Diagnostic::UnknownFunction(import.name.to_string()));
+                                continue;
+                            }
+                        };
+                        if let Some(Some(f)) = types.get(idx as usize) {
+                            if f.params() != params || f.results() != results {
+                                diagnostics.push(Diagnostic::SignatureMismatch {
+                                    name: import.name.to_string(),
+                                    expected: signature(params, results),
+                                    found: signature(f.params(), f.results()),
+                                });
+                            }
+                        }
+                    }
+                }
+                Payload::FunctionSection(reader) => {
+                    for idx in reader {
+                        functions.push(idx?);
+                    }
+                }
+                Payload::ExportSection(reader) => {
+                    for export in reader {
+                        let export = export?;
+                        exports.insert(export.name.to_string(), (export.kind, export.index));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // the WACC functions read strings from the exported memory
+        if !matches!(exports.get(MEMORY_EXPORT), Some((ExternalKind::Memory, _))) {
+            diagnostics.push(Diagnostic::MissingMemoryExport);
+        }
+
+        for name in &self.entry_points {
+            let idx = match exports.get(name) {
+                Some((ExternalKind::Func, idx)) => *idx,
+                _ => {
+                    diagnostics.push(Diagnostic::MissingEntryPoint(name.clone()));
+                    continue;
+                }
+            };
+            let ty = functions
+                .get(idx as usize)
+                .and_then(|t| types.get(*t as usize))
+                .and_then(|t| t.as_ref());
+            if let Some(f) = ty {
+                if !f.params().is_empty() || f.results() != [ValType::I32] {
+                    diagnostics.push(Diagnostic::EntryPointSignature {
+                        name: name.clone(),
+                        found: signature(f.params(), f.results()),
+                    });
+                }
+            }
+        }
+
+        Ok(diagnostics)
+    }
+}
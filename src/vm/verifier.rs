@@ -0,0 +1,428 @@
+// SPDX-License-Identifier: FSL-1.1
+use crate::{
+    error::VmError,
+    vm::{
+        analysis::{analyze, Analysis, State},
+        lockset::{Lock, LockOutcome, LockSetVerification, Satisfied},
+        Builder, Context, CostSchedule, Limits, Phase, RunOutcome, Runtime, SignaturePolicy,
+    },
+    Error, Pairs, Stack, Value,
+};
+use std::{collections::BTreeSet, fmt, sync::OnceLock, time::Duration};
+
+pub use crate::vm::context::DEFAULT_MEMORY_LIMIT;
+
+/// The default entry point of unlock scripts
+pub const UNLOCK_FUNC: &str = "for_great_justice";
+
+/// The default entry point of lock scripts
+pub const LOCK_FUNC: &str = "move_every_zig";
+
+/// The result of verifying an unlock script against a lock script
+#[derive(Clone, Debug, PartialEq)]
+pub struct Verification {
+    /// True if the unlock script succeeded, the lock script succeeded and a
+    /// SUCCESS marker is on top of the return stack
+    pub verdict: bool,
+    /// The outcome of the unlock script
+    pub unlock: RunOutcome,
+    /// The outcome of the lock script, None if the unlock script failed
+    pub lock: Option<RunOutcome>,
+    /// The values left on the parameter stack
+    pub pstack: Vec<Value>,
+    /// The values left on the return stack
+    pub rstack: Vec<Value>,
+}
+
+/// A key a script reads that is missing from the state it is read from
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MissingKey {
+    /// The phase of the script that reads the key
+    pub phase: Phase,
+    /// The state the key is missing from
+    pub state: State,
+    /// The full key-path
+    pub key: String,
+    /// The name of the WACC function that reads the key
+    pub function: String,
+}
+
+impl fmt::Display for MissingKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} is missing from the {} state, the {} script reads it with {}",
+            self.key, self.state, self.phase, self.function
+        )
+    }
+}
+
+/// Runs an unlock script followed by a lock script the way the WACC
+/// execution model expects: the unlock script reads the proposed state and
+/// leaves its proofs on the parameter stack, then the lock script checks
+/// them against the current state and the proposed state. Each script is
+/// built for its [`Phase`].
+pub struct Verifier<'a>
+{
+    unlock: Vec<u8>,
+    lock: Vec<u8>,
+    unlock_func: String,
+    lock_func: String,
+    current: Option<&'a dyn Pairs>,
+    proposed: Option<&'a dyn Pairs>,
+    context: String,
+    fuel: Option<u64>,
+    deadline: Option<Duration>,
+    memory_limit: usize,
+    limits: Option<Limits>,
+    policy: SignaturePolicy,
+    costs: CostSchedule,
+    runtime: Option<&'a Runtime>,
+    preflight: bool,
+    unlock_analysis: OnceLock<Result<Analysis, Error>>,
+    lock_analysis: OnceLock<Result<Analysis, Error>>,
+}
+
+impl Default for Verifier<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Verifier<'a>
+{
+    /// create a new verifier
+    pub fn new() -> Self {
+        Self {
+            unlock: Vec::default(),
+            lock: Vec::default(),
+            unlock_func: UNLOCK_FUNC.to_string(),
+            lock_func: LOCK_FUNC.to_string(),
+            current: None,
+            proposed: None,
+            context: "/".to_string(),
+            fuel: None,
+            deadline: None,
+            memory_limit: DEFAULT_MEMORY_LIMIT,
+            limits: None,
+            policy: SignaturePolicy::default(),
+            costs: CostSchedule::default(),
+            runtime: None,
+            preflight: false,
+            unlock_analysis: OnceLock::new(),
+            lock_analysis: OnceLock::new(),
+        }
+    }
+
+    /// Set the unlock script and its entry point
+    pub fn with_unlock(mut self, bytes: impl AsRef<[u8]>, func: &str) -> Self {
+        self.unlock = bytes.as_ref().to_vec();
+        self.unlock_func = func.to_string();
+        self.unlock_analysis = OnceLock::new();
+        self
+    }
+
+    /// Set the lock script and its entry point
+    pub fn with_lock(mut self, bytes: impl AsRef<[u8]>, func: &str) -> Self {
+        self.lock = bytes.as_ref().to_vec();
+        self.lock_func = func.to_string();
+        self.lock_analysis = OnceLock::new();
+        self
+    }
+
+    /// Set the key-value store of the current state
+    pub fn with_current(mut self, current: &'a dyn Pairs) -> Self {
+        self.current = Some(current);
+        self
+    }
+
+    /// Set the key-value store of the proposed state update
+    pub fn with_proposed(mut self, proposed: &'a dyn Pairs) -> Self {
+        self.proposed = Some(proposed);
+        self
+    }
+
+    /// Set the context key-path, defaults to "/"
+    pub fn with_context(mut self, context: &str) -> Self {
+        self.context = context.to_string();
+        self
+    }
+
+    /// Enables the use of fuel and establishes the fuel limit for both scripts
+    /// combined
+    pub fn with_fuel(mut self, fuel: u64) -> Self {
+        self.fuel = Some(fuel);
+        self
+    }
+
+    /// Establishes the wall-clock limit for each script, this requires a
+    /// [`Runtime`] with deadlines enabled
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Set the linear memory limit for each script
+    pub fn with_memory_limit(mut self, limit: usize) -> Self {
+        self.memory_limit = limit;
+        self
+    }
+
+    /// Set the static limits the scripts are checked against before they are
+    /// compiled
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = Some(limits);
+        self
+    }
+
+    /// Set the signature algorithm policy
+    pub fn with_policy(mut self, policy: SignaturePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Set the fuel cost schedule of the WACC functions
+    pub fn with_cost_schedule(mut self, costs: CostSchedule) -> Self {
+        self.costs = costs;
+        self
+    }
+
+    /// Use the shared [`Runtime`] for both scripts
+    pub fn with_runtime(mut self, runtime: &'a Runtime) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+
+    /// Check that the keys the unlock script reads exist before running the
+    /// scripts, failing with [`VmError::MissingKeys`] if they don't. The keys
+    /// the lock scripts read aren't required since a lock script may try
+    /// alternatives, use [`Verifier::preflight`] to list those. The unlock
+    /// script is analyzed once, and if it can't be analyzed the scripts are
+    /// run without the check.
+    pub fn with_preflight(mut self, preflight: bool) -> Self {
+        self.preflight = preflight;
+        self
+    }
+
+    /// Lists the keys the unlock and lock scripts read that are missing from
+    /// the state, without running them. Only the key-paths that can be found
+    /// statically are checked, see [`crate::vm::analysis::analyze`].
+    pub fn preflight(&self) -> Result<Vec<MissingKey>, Error> {
+        let current = self.current.ok_or(VmError::MissingContext)?;
+        let proposed = self.proposed.ok_or(VmError::MissingContext)?;
+        let mut missing = self.missing(Phase::Unlock, self.analysis(Phase::Unlock)?, current, proposed);
+        missing.extend(self.missing(Phase::Lock, self.analysis(Phase::Lock)?, current, proposed));
+        Ok(missing)
+    }
+
+    /// Runs the unlock script then, if it succeeded, the lock script
+    pub fn verify(&self) -> Result<Verification, Error> {
+        let current = self.current.ok_or(VmError::MissingContext)?;
+        let proposed = self.proposed.ok_or(VmError::MissingContext)?;
+        self.check_unlock(current, proposed)?;
+        let mut pstack: Vec<Value> = Vec::default();
+        let mut rstack: Vec<Value> = Vec::default();
+
+        // the unlock script only sees the proposed state
+        let unlock = self.run_unlock(proposed, &mut pstack, &mut rstack)?;
+
+        // the lock script gets the fuel left over by the unlock script
+        let lock = match unlock.verdict {
+            true => {
+                let fuel = unlock.fuel.map(|f| f.remaining);
+                let lock = self
+                    .builder(Phase::Lock, &self.lock, fuel)
+                    .with_context(self.context(&self.context, current, proposed, &mut pstack, &mut rstack)?)
+                    .try_build()?
+                    .execute(&self.lock_func)?;
+                Some(lock)
+            }
+            false => None,
+        };
+
+        let verdict = unlock.verdict
+            && lock.as_ref().is_some_and(|l| l.verdict)
+            && matches!(rstack.top(), Some(Value::Success(_)));
+
+        Ok(Verification {
+            verdict,
+            unlock,
+            lock,
+            pstack,
+            rstack,
+        })
+    }
+
+    /// Runs the unlock script then, if it succeeded, each of the lock scripts
+    /// in order of precedence against a copy of the stacks the unlock script
+    /// left, until one of them is satisfied. The lock scripts share the fuel
+    /// left over by the unlock script. A lock script that can't be built or
+    /// aborts is recorded as failed and the next one is run.
+    pub fn verify_locks(&self, locks: &[Lock]) -> Result<LockSetVerification, Error> {
+        let current = self.current.ok_or(VmError::MissingContext)?;
+        let proposed = self.proposed.ok_or(VmError::MissingContext)?;
+        self.check_unlock(current, proposed)?;
+        let mut pstack: Vec<Value> = Vec::default();
+        let mut rstack: Vec<Value> = Vec::default();
+
+        // the unlock script only sees the proposed state
+        let unlock = self.run_unlock(proposed, &mut pstack, &mut rstack)?;
+        let mut result = LockSetVerification {
+            unlock,
+            locks: Vec::default(),
+            satisfied: None,
+        };
+        if !result.unlock.verdict {
+            return Ok(result);
+        }
+
+        let mut fuel = result.unlock.fuel.map(|f| f.remaining);
+        for (index, lock) in locks.iter().enumerate() {
+            // every lock starts from what the unlock script left
+            let mut lock_pstack = pstack.clone();
+            let mut lock_rstack = rstack.clone();
+            let context = lock.context.as_deref().unwrap_or(&self.context);
+            let outcome = match self.context(context, current, proposed, &mut lock_pstack, &mut lock_rstack) {
+                Ok(ctx) => match self.builder(Phase::Lock, &lock.bytes, fuel).with_context(ctx).try_build() {
+                    Ok(mut instance) => {
+                        let outcome = instance.execute(&lock.func);
+                        // the fuel burned by an aborted run is still spent
+                        fuel = instance.fuel().map(|f| f.remaining);
+                        outcome
+                    }
+                    Err(e) => Err(e),
+                },
+                Err(e) => Err(e),
+            };
+            let outcome = match outcome {
+                Ok(outcome) => LockOutcome::Ran(outcome),
+                Err(e) => LockOutcome::Failed(e.to_string()),
+            };
+
+            let satisfied = outcome.verdict() && matches!(lock_rstack.top(), Some(Value::Success(_)));
+            if let (true, Some(outcome)) = (satisfied, outcome.outcome()) {
+                // the last check to pass put the SUCCESS marker on the stack
+                let check = outcome.checks.iter().rev().find(|c| c.passed).cloned();
+                result.satisfied = Some(Satisfied { index, check });
+            }
+            result.locks.push(outcome);
+            if satisfied {
+                break;
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn check_unlock(&self, current: &dyn Pairs, proposed: &dyn Pairs) -> Result<(), Error> {
+        if !self.preflight {
+            return Ok(());
+        }
+        // the check is an optimization, a script that can't be analyzed still runs
+        let analysis = match self.analysis(Phase::Unlock) {
+            Ok(analysis) => analysis,
+            Err(_) => return Ok(()),
+        };
+        let missing = self.missing(Phase::Unlock, analysis, current, proposed);
+        match missing.is_empty() {
+            true => Ok(()),
+            false => Err(VmError::MissingKeys(missing).into()),
+        }
+    }
+
+    /// Gets the analysis of the script for the phase, analyzing it once
+    fn analysis(&self, phase: Phase) -> Result<&Analysis, Error> {
+        let (cell, bytes) = match phase {
+            Phase::Unlock => (&self.unlock_analysis, &self.unlock),
+            _ => (&self.lock_analysis, &self.lock),
+        };
+        cell.get_or_init(|| analyze(bytes)).as_ref().map_err(Clone::clone)
+    }
+
+    fn missing(
+        &self,
+        phase: Phase,
+        analysis: &Analysis,
+        current: &dyn Pairs,
+        proposed: &dyn Pairs,
+    ) -> Vec<MissingKey> {
+        let mut missing = Vec::default();
+        let mut seen = BTreeSet::default();
+        for read in analysis.reads(&self.context) {
+            // the unlock script only sees the proposed state
+            let state = match phase {
+                Phase::Unlock => State::Proposed,
+                _ => read.state,
+            };
+            // a key read by several functions is reported once
+            if !seen.insert((state, read.key.clone())) {
+                continue;
+            }
+            let pairs = match state {
+                State::Current => current,
+                State::Proposed => proposed,
+            };
+            if pairs.get(&read.key).is_none() {
+                missing.push(MissingKey {
+                    phase,
+                    state,
+                    key: read.key,
+                    function: read.function,
+                });
+            }
+        }
+        missing
+    }
+
+    fn run_unlock(
+        &self,
+        proposed: &dyn Pairs,
+        pstack: &mut dyn Stack,
+        rstack: &mut dyn Stack,
+    ) -> Result<RunOutcome, Error> {
+        self.builder(Phase::Unlock, &self.unlock, self.fuel)
+            .with_context(self.context(&self.context, proposed, proposed, pstack, rstack)?)
+            .try_build()?
+            .execute(&self.unlock_func)
+    }
+
+    fn builder<'b>(&self, phase: Phase, bytes: &[u8], fuel: Option<u64>) -> Builder<'b>
+    where
+        'a: 'b,
+    {
+        let mut builder = Builder::new().with_phase(phase).with_bytes(bytes);
+        if let Some(fuel) = fuel {
+            builder = builder.with_fuel(fuel);
+        }
+        if let Some(deadline) = self.deadline {
+            builder = builder.with_deadline(deadline);
+        }
+        if let Some(limits) = self.limits {
+            builder = builder.with_limits(limits);
+        }
+        if let Some(runtime) = self.runtime {
+            builder = builder.with_runtime(runtime);
+        }
+        builder
+    }
+
+    fn context<'b>(
+        &self,
+        path: &str,
+        current: &'b dyn Pairs,
+        proposed: &'b dyn Pairs,
+        pstack: &'b mut dyn Stack,
+        rstack: &'b mut dyn Stack,
+    ) -> Result<Context<'b>, Error> {
+        Context::builder()
+            .with_current(current)
+            .with_proposed(proposed)
+            .with_pstack(pstack)
+            .with_rstack(rstack)
+            .with_context(path)
+            .with_memory_limit(self.memory_limit)
+            .with_policy(self.policy.clone())
+            .with_cost_schedule(self.costs)
+            .try_build()
+    }
+}
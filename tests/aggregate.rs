@@ -0,0 +1,154 @@
+// SPDX-License-Identifier: FSL-1.1
+#![cfg(feature = "wat")]
+mod common;
+
+use blsful::{AggregateSignature, Bls12381G1Impl, SecretKey, Signature, SignatureSchemes};
+use common::{load_wast, Kvp, Stk};
+use multicodec::Codec;
+use multikey::mk;
+use multisig::ms;
+use std::collections::BTreeSet;
+use wacc::{storage::{Pairs, Stack}, vm::{Builder, Context, Instance, Value}};
+use wasmtime::AsContextMut;
+
+const MESSAGE: &[u8] = b"for great justice, move every zig!";
+
+fn test_example<'a>(
+    expected: bool,
+    current: &'a Kvp,
+    proposed: &'a Kvp,
+    pstack: &'a mut Stk,
+    rstack: &'a mut Stk,
+) -> Instance<'a> {
+    // build the context
+    let context = Context::builder()
+        .with_current(current)
+        .with_proposed(proposed)
+        .with_pstack(pstack)
+        .with_rstack(rstack)
+        .try_build()
+        .unwrap();
+
+    // construct the instance
+    let mut instance = Builder::new()
+        .with_context(context)
+        .with_bytes(load_wast("aggregate_lock.wast"))
+        .try_build()
+        .unwrap();
+
+    // execute the instance
+    let result = instance.run("move_every_zig").unwrap().verdict;
+
+    assert_eq!(expected, result);
+    instance
+}
+
+fn signers(n: usize) -> Vec<SecretKey<Bls12381G1Impl>> {
+    (0..n).map(|_| SecretKey::<Bls12381G1Impl>::new()).collect()
+}
+
+fn encode_pubkey(sk: &SecretKey<Bls12381G1Impl>) -> Vec<u8> {
+    let pk_bytes = Vec::<u8>::from(&sk.public_key());
+    mk::Builder::new(Codec::Bls12381G1Pub)
+        .with_key_bytes(&pk_bytes)
+        .try_build()
+        .unwrap()
+        .into()
+}
+
+fn encode_aggregate(sigs: &[Signature<Bls12381G1Impl>]) -> Vec<u8> {
+    let agg = AggregateSignature::<Bls12381G1Impl>::from_signatures(sigs).unwrap();
+    ms::Builder::new(Codec::Bls12381G1Msig)
+        .with_signature_bytes(&Vec::<u8>::from(&agg))
+        .try_build()
+        .unwrap()
+        .into()
+}
+
+fn check(kvp_lock: &Kvp, kvp_unlock: &Kvp, sig: Vec<u8>, expected: bool) {
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    pstack.push(sig.into());
+
+    let mut instance = test_example(expected, kvp_lock, kvp_unlock, &mut pstack, &mut rstack);
+    let mut ctx = instance.store.as_context_mut();
+    let context = ctx.data_mut();
+    assert_eq!(1, context.rstack.len());
+    if expected {
+        assert_eq!(0, context.pstack.len());
+        assert_eq!(context.rstack.top(), Some(Value::Success(0)));
+    } else {
+        assert_eq!(1, context.pstack.len());
+    }
+}
+
+#[test]
+fn test_aggregate_shared_message() {
+    let sks = signers(3);
+
+    // the signers' public keys are committed to under the prefix
+    let mut kvp_lock = Kvp::default();
+    for (i, sk) in sks.iter().enumerate() {
+        let _ = kvp_lock.put(&format!("/signers/{i}"), &encode_pubkey(sk).into());
+    }
+
+    // all signers signed the same message
+    let mut kvp_unlock = Kvp::default();
+    let _ = kvp_unlock.put("/entry/", &MESSAGE.into());
+
+    let sigs: Vec<_> = sks
+        .iter()
+        .map(|sk| sk.sign(SignatureSchemes::MessageAugmentation, MESSAGE).unwrap())
+        .collect();
+    check(&kvp_lock, &kvp_unlock, encode_aggregate(&sigs), true);
+
+    // an aggregate missing one of the signers fails
+    check(&kvp_lock, &kvp_unlock, encode_aggregate(&sigs[..2]), false);
+}
+
+#[test]
+fn test_aggregate_proof_of_possession_rejected() {
+    let sks = signers(2);
+
+    let mut kvp_lock = Kvp::default();
+    for (i, sk) in sks.iter().enumerate() {
+        let _ = kvp_lock.put(&format!("/signers/{i}"), &encode_pubkey(sk).into());
+    }
+
+    let mut kvp_unlock = Kvp::default();
+    let _ = kvp_unlock.put("/entry/", &MESSAGE.into());
+
+    // without a proof of possession for every key a valid aggregate is still rejected
+    let sigs: Vec<_> = sks
+        .iter()
+        .map(|sk| sk.sign(SignatureSchemes::ProofOfPossession, MESSAGE).unwrap())
+        .collect();
+    check(&kvp_lock, &kvp_unlock, encode_aggregate(&sigs), false);
+}
+
+#[test]
+fn test_aggregate_per_signer_messages() {
+    let sks = signers(2);
+
+    // the signers' public keys are committed to under the prefix
+    let mut kvp_lock = Kvp::default();
+    let mut kvp_unlock = Kvp::default();
+    let mut sigs = Vec::default();
+    for (i, sk) in sks.iter().enumerate() {
+        let msg = format!("signer {i} approves");
+        let _ = kvp_lock.put(&format!("/signers/{i}"), &encode_pubkey(sk).into());
+        let _ = kvp_unlock.put(&format!("/entry/{i}"), &msg.as_bytes().into());
+        sigs.push(sk.sign(SignatureSchemes::Basic, msg.as_bytes()).unwrap());
+    }
+    check(&kvp_lock, &kvp_unlock, encode_aggregate(&sigs), true);
+
+    // the shared message is recorded as read even though it is absent
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    pstack.push(encode_aggregate(&sigs).into());
+    let instance = test_example(true, &kvp_lock, &kvp_unlock, &mut pstack, &mut rstack);
+    assert_eq!(
+        BTreeSet::from(["/entry/".to_string(), "/entry/0".to_string(), "/entry/1".to_string()]),
+        instance.store.data().access().proposed
+    );
+}
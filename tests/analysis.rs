@@ -0,0 +1,89 @@
+// SPDX-License-Identifier: FSL-1.1
+#![cfg(feature = "wat")]
+mod common;
+
+use common::load_wast;
+use std::collections::BTreeSet;
+use wacc::vm::{analyze, KeyRef, Read, State};
+
+fn key(function: &str, arg: usize, key: &str, branched: bool) -> KeyRef {
+    KeyRef {
+        function: function.to_string(),
+        arg,
+        key: key.to_string(),
+        branched,
+    }
+}
+
+#[test]
+fn test_lock_key_paths() {
+    let analysis = analyze(&load_wast("lock.wast")).unwrap();
+    let functions: BTreeSet<String> = ["_check_preimage", "_check_signature"].map(String::from).into();
+    assert_eq!(functions, analysis.functions);
+    assert_eq!(
+        vec![
+            key("_check_signature", 0, "/tpubkey", false),
+            key("_check_signature", 1, "/entry/", false),
+            key("_check_signature", 0, "/pubkey", false),
+            key("_check_signature", 1, "/entry/", false),
+            key("_check_preimage", 0, "/hash", false),
+        ],
+        analysis.keys
+    );
+    let paths: BTreeSet<String> = ["/entry/", "/tpubkey", "/pubkey", "/hash"].map(String::from).into();
+    assert_eq!(paths, analysis.key_paths("/"));
+}
+
+#[test]
+fn test_branched_key_paths() {
+    let analysis = analyze(&load_wast("fork_lock.wast")).unwrap();
+    assert!(analysis.keys.contains(&key("_branch", 0, "pubkey", false)));
+    assert!(analysis.keys.contains(&key("_check_signature", 0, "pubkey", true)));
+    assert!(analysis.keys.contains(&key("_check_eq", 0, "vlad", true)));
+    let paths: BTreeSet<String> = ["/entry/", "/child/pubkey", "/child/vlad"].map(String::from).into();
+    assert_eq!(paths, analysis.key_paths("/child/"));
+}
+
+#[test]
+fn test_reads() {
+    let read = |state, key: &str, function: &str| Read {
+        state,
+        key: key.to_string(),
+        function: function.to_string(),
+    };
+    let analysis = analyze(&load_wast("fork_lock.wast")).unwrap();
+    assert_eq!(
+        vec![
+            read(State::Current, "/child/pubkey", "_check_signature"),
+            read(State::Proposed, "/entry/", "_check_signature"),
+            read(State::Current, "/child/vlad", "_check_eq"),
+        ],
+        analysis.reads("/child/")
+    );
+}
+
+#[test]
+fn test_unknown_key_paths() {
+    // the key-path length is computed so it can't be resolved statically
+    let script = r#"
+        (module
+          (import "wacc" "_check_eq" (func $check_eq (param i32 i32) (result i32)))
+          (func $main (export "move_zig") (param) (result i32)
+            i32.const 0
+            i32.const 3
+            i32.const 4
+            i32.add
+            call $check_eq
+          )
+          (memory (export "memory") 1)
+          (data (i32.const 0) "/pubkey")
+        )"#;
+    let analysis = analyze(script.as_bytes()).unwrap();
+    assert!(analysis.functions.contains("_check_eq"));
+    assert!(analysis.keys.is_empty());
+}
+
+#[test]
+fn test_invalid_script() {
+    assert!(analyze(b"(module").is_err());
+}
@@ -0,0 +1,276 @@
+// SPDX-License-Identifier: FSL-1.1
+#![cfg(feature = "wat")]
+mod common;
+
+use common::{load_wast, Kvp, Stk};
+use futures::executor::block_on;
+use std::collections::BTreeMap;
+use wacc::{
+    error::VmError,
+    storage::{
+        AsyncPairs, AsyncStack, BoxFuture, Pairs, PrefetchPairs, StackBuffer, Threaded, ThreadedPairs,
+        ThreadedStack,
+    },
+    vm::{Builder, Context, Runtime, Value},
+    Error,
+};
+
+fn context<'a>(
+    current: &'a dyn ThreadedPairs,
+    proposed: &'a dyn ThreadedPairs,
+    pstack: &'a mut dyn ThreadedStack,
+    rstack: &'a mut dyn ThreadedStack,
+) -> Context<'a, Threaded> {
+    Context::threaded_builder()
+        .with_current(current)
+        .with_proposed(proposed)
+        .with_pstack(pstack)
+        .with_rstack(rstack)
+        .try_build()
+        .unwrap()
+}
+
+#[derive(Default)]
+struct AsyncKvp {
+    pub pairs: BTreeMap<String, Value>,
+}
+
+impl AsyncPairs for AsyncKvp {
+    /// get a value associated with the key
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<Value>> {
+        Box::pin(async move { self.pairs.get(key).cloned() })
+    }
+
+    /// add a key-value pair to the storage, return previous value if overwritten
+    fn put<'a>(&'a mut self, key: &'a str, value: &'a Value) -> BoxFuture<'a, Option<Value>> {
+        Box::pin(async move { self.pairs.insert(key.to_string(), value.clone()) })
+    }
+
+    /// get the keys that start with the prefix, in order
+    fn keys<'a>(&'a self, prefix: &'a str) -> BoxFuture<'a, Vec<String>> {
+        Box::pin(async move { self.pairs.keys().filter(|k| k.starts_with(prefix)).cloned().collect() })
+    }
+}
+
+#[derive(Default)]
+struct AsyncStk {
+    pub stack: Vec<Value>
+}
+
+impl AsyncStack for AsyncStk {
+    /// push a value onto the stack
+    fn push(&mut self, value: Value) -> BoxFuture<'_, ()> {
+        Box::pin(async move { self.stack.push(value) })
+    }
+
+    /// remove the last top value from the stack
+    fn pop(&mut self) -> BoxFuture<'_, Option<Value>> {
+        Box::pin(async move { self.stack.pop() })
+    }
+
+    /// get a reference to the top value on the stack
+    fn top(&self) -> BoxFuture<'_, Option<Value>> {
+        Box::pin(async move { self.stack.last().cloned() })
+    }
+
+    /// peek at the item at the given index
+    fn peek(&self, idx: usize) -> BoxFuture<'_, Option<Value>> {
+        Box::pin(async move {
+            if idx >= self.stack.len() {
+                return None;
+            }
+            Some(self.stack[self.stack.len() - 1 - idx].clone())
+        })
+    }
+
+    /// return the number of values on the stack
+    fn len(&self) -> BoxFuture<'_, usize> {
+        Box::pin(async move { self.stack.len() })
+    }
+
+    /// return if the stack is empty
+    fn is_empty(&self) -> BoxFuture<'_, bool> {
+        Box::pin(async move { self.stack.is_empty() })
+    }
+}
+
+#[test]
+fn test_run_async() {
+    // the async key-value pair store with the preimage data
+    let mut kvp_unlock = AsyncKvp::default();
+    let _ = block_on(kvp_unlock.put("/entry/proof", &"for great justice, move every zig!".to_string().into()));
+    // the async key-value pair store with the sha3 256 hash of the preimage
+    let mut kvp_lock = AsyncKvp::default();
+    let hash: Value = hex::decode("16206b761d3b2e7675e088e337a82207b55711d3957efdb877a3d261b0ca2c38e201").unwrap().into();
+    let _ = block_on(kvp_lock.put("/hash", &hash));
+    // the async stacks
+    let mut apstack = AsyncStk::default();
+    let mut arstack = AsyncStk::default();
+
+    block_on(async {
+        let unlock = PrefetchPairs::new(&kvp_unlock);
+        let lock = PrefetchPairs::new(&kvp_lock);
+        let mut pstack = StackBuffer::load(&apstack).await;
+        let mut rstack = StackBuffer::load(&arstack).await;
+
+        { // unlock
+            let mut instance = Builder::new()
+                .with_async()
+                .with_context(context(&unlock, &unlock, &mut pstack, &mut rstack))
+                .with_bytes(load_wast("preimage_unlock.wast"))
+                .try_build()
+                .unwrap();
+            assert!(instance.run_async("for_great_justice").await.unwrap().verdict);
+        }
+
+        { // lock
+            let mut instance = Builder::new()
+                .with_async()
+                .with_context(context(&lock, &unlock, &mut pstack, &mut rstack))
+                .with_bytes(load_wast("preimage_lock.wast"))
+                .try_build()
+                .unwrap();
+            assert!(instance.run_async("move_every_zig").await.unwrap().verdict);
+        }
+
+        pstack.store(&mut apstack).await;
+        rstack.store(&mut arstack).await;
+    });
+
+    // the preimage was consumed and the check succeeded
+    assert!(apstack.stack.is_empty());
+    assert_eq!(vec![Value::Success(0)], arstack.stack);
+}
+
+#[test]
+fn test_run_async_with_runtime() {
+    let runtime = Runtime::builder().with_async().with_fuel().try_build().unwrap();
+    let kvp = AsyncKvp::default();
+    let pairs = PrefetchPairs::new(&kvp);
+    let mut pstack = StackBuffer::default();
+    let mut rstack = StackBuffer::default();
+    let mut instance = Builder::new()
+        .with_runtime(&runtime)
+        .with_async()
+        .with_fuel(1_000_000)
+        .with_context(context(&pairs, &pairs, &mut pstack, &mut rstack))
+        .with_bytes(load_wast("log.wast"))
+        .try_build()
+        .unwrap();
+    assert!(block_on(instance.run_async("move_every_zig")).unwrap().verdict);
+    assert_eq!(b"Hello World!\n".to_vec(), instance.log());
+    assert!(instance.fuel().unwrap().consumed > 0);
+}
+
+#[test]
+fn test_run_async_consumes_sync_fuel() {
+    let preimage: Value = "for great justice, move every zig!".to_string().into();
+
+    // run the unlock script synchronously
+    let mut kvp = Kvp::default();
+    let _ = kvp.put("/entry/proof", &preimage);
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    let ctx = Context::builder()
+        .with_current(&kvp)
+        .with_proposed(&kvp)
+        .with_pstack(&mut pstack)
+        .with_rstack(&mut rstack)
+        .try_build()
+        .unwrap();
+    let mut instance = Builder::new()
+        .with_fuel(1_000_000)
+        .with_context(ctx)
+        .with_bytes(load_wast("preimage_unlock.wast"))
+        .try_build()
+        .unwrap();
+    assert!(instance.run("for_great_justice").unwrap().verdict);
+    let sync = instance.fuel().unwrap();
+
+    // run the same script asynchronously
+    let mut akvp = AsyncKvp::default();
+    let _ = block_on(akvp.put("/entry/proof", &preimage));
+    let pairs = PrefetchPairs::new(&akvp);
+    let mut pstack = StackBuffer::default();
+    let mut rstack = StackBuffer::default();
+    let mut instance = Builder::new()
+        .with_async()
+        .with_fuel(1_000_000)
+        .with_context(context(&pairs, &pairs, &mut pstack, &mut rstack))
+        .with_bytes(load_wast("preimage_unlock.wast"))
+        .try_build()
+        .unwrap();
+    assert!(block_on(instance.run_async("for_great_justice")).unwrap().verdict);
+
+    // prefetching the keys doesn't charge for the string parameters again
+    assert_eq!(sync.consumed, instance.fuel().unwrap().consumed);
+}
+
+#[test]
+fn test_async_requires_runtime_support() {
+    let runtime = Runtime::builder().try_build().unwrap();
+    let kvp = AsyncKvp::default();
+    let pairs = PrefetchPairs::new(&kvp);
+    let mut pstack = StackBuffer::default();
+    let mut rstack = StackBuffer::default();
+    let result = Builder::new()
+        .with_runtime(&runtime)
+        .with_async()
+        .with_context(context(&pairs, &pairs, &mut pstack, &mut rstack))
+        .with_bytes(load_wast("log.wast"))
+        .try_build();
+    assert!(matches!(result, Err(Error::Vm(VmError::IncompatibleRuntime(_)))));
+}
+
+#[test]
+fn test_sync_run_requires_sync_instance() {
+    let runtime = Runtime::builder().with_async().try_build().unwrap();
+    let kvp = AsyncKvp::default();
+    let pairs = PrefetchPairs::new(&kvp);
+    let mut pstack = StackBuffer::default();
+    let mut rstack = StackBuffer::default();
+    let mut instance = Builder::new()
+        .with_runtime(&runtime)
+        .with_async()
+        .with_context(context(&pairs, &pairs, &mut pstack, &mut rstack))
+        .with_bytes(load_wast("log.wast"))
+        .try_build()
+        .unwrap();
+    let result = instance.run("move_every_zig");
+    assert!(matches!(result, Err(Error::Vm(VmError::AsyncRequired))));
+}
+
+#[test]
+fn test_async_run_requires_async_instance() {
+    let kvp = AsyncKvp::default();
+    let pairs = PrefetchPairs::new(&kvp);
+    let mut pstack = StackBuffer::default();
+    let mut rstack = StackBuffer::default();
+    let mut instance = Builder::new()
+        .with_context(context(&pairs, &pairs, &mut pstack, &mut rstack))
+        .with_bytes(load_wast("log.wast"))
+        .try_build()
+        .unwrap();
+    let result = block_on(instance.run_async("move_every_zig"));
+    assert!(matches!(result, Err(Error::Vm(VmError::AsyncUnsupported))));
+}
+
+#[test]
+fn test_async_requires_threaded_storage() {
+    let kvp = Kvp::default();
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    let ctx = Context::builder()
+        .with_current(&kvp)
+        .with_proposed(&kvp)
+        .with_pstack(&mut pstack)
+        .with_rstack(&mut rstack)
+        .try_build()
+        .unwrap();
+    let result = Builder::new()
+        .with_async()
+        .with_context(ctx)
+        .with_bytes(load_wast("log.wast"))
+        .try_build();
+    assert!(matches!(result, Err(Error::Vm(VmError::IncompatibleRuntime(_)))));
+}
@@ -1,26 +1,11 @@
 // SPDX-License-Identifier: FSL-1.1
-use std::{collections::BTreeMap, fs::read, path::PathBuf};
-use wacc::{storage::{Pairs, Stack}, vm::{Builder, Context, Instance, Value}};
-use wasmtime::{AsContextMut, StoreLimitsBuilder};
+mod common;
 
-const MEMORY_LIMIT: usize = 1 << 22; /* 4MB */
-
-fn load_wasm(file_name: &str) -> Vec<u8> {
-    let mut pb = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-    pb.push("target");
-    pb.push(file_name);
-    println!("trying to load: {:?}", pb.as_os_str());
-    read(&pb).unwrap_or_else(|_| panic!("Error loading file {file_name}"))
-}
-
-fn load_wast(file_name: &str) -> Vec<u8> {
-    let mut pb = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-    pb.push("examples");
-    pb.push("wast");
-    pb.push(file_name);
-    println!("trying to load: {:?}", pb.as_os_str());
-    read(&pb).unwrap_or_else(|_| panic!("Error loading file {file_name}"))
-}
+#[cfg(feature = "wat")]
+use common::load_wast;
+use common::{load_wasm, Kvp, Stk};
+use wacc::vm::{Builder, Context, Instance};
+use wasmtime::AsContextMut;
 
 fn test_example<'a>(
     script: Vec<u8>,
@@ -31,21 +16,14 @@ fn test_example<'a>(
     rstack: &'a mut Stk,
 ) -> Instance<'a> {
     // build the context
-    let context = Context {
-        current,
-        proposed,
-        pstack,
-        rstack,
-        check_count: 0,
-        write_idx: 0,
-        context: "/forks/child/".to_string(),
-        log: Vec::default(),
-        limiter: StoreLimitsBuilder::new()
-            .memory_size(MEMORY_LIMIT)
-            .instances(2)
-            .memories(1)
-            .build(),
-    };
+    let context = Context::builder()
+        .with_current(current)
+        .with_proposed(proposed)
+        .with_pstack(pstack)
+        .with_rstack(rstack)
+        .with_context("/forks/child/")
+        .try_build()
+        .unwrap();
 
     // construct the instance
     let mut instance = Builder::new()
@@ -55,69 +33,13 @@ fn test_example<'a>(
         .unwrap();
 
     // execute the instance
-    let result = instance.run("move_every_zig").unwrap();
+    let result = instance.run("move_every_zig").unwrap().verdict;
 
     assert_eq!(expected, result);
     instance
 }
 
-#[derive(Default)]
-struct Kvp {
-    pub pairs: BTreeMap<String, Value>,
-}
-
-impl Pairs for Kvp {
-    /// get a value associated with the key
-    fn get(&self, key: &str) -> Option<Value> {
-        self.pairs.get(key).cloned()
-    }
-
-    /// add a key-value pair to the storage, return previous value if overwritten
-    fn put(&mut self, key: &str, value: &Value) -> Option<Value> {
-        self.pairs.insert(key.to_string(), value.clone())
-    }
-}
-
-#[derive(Default)]
-struct Stk {
-    pub stack: Vec<Value>
-}
-
-impl Stack for Stk {
-    /// push a value onto the stack
-    fn push(&mut self, value: Value) {
-        self.stack.push(value);
-    }
-
-    /// remove the last top value from the stack
-    fn pop(&mut self) -> Option<Value> {
-        self.stack.pop()
-    }
-
-    /// get a reference to the top value on the stack 
-    fn top(&self) -> Option<Value> {
-        self.stack.last().cloned()
-    }
-
-    /// peek at the item at the given index
-    fn peek(&self, idx: usize) -> Option<Value> {
-        if idx >= self.stack.len() {
-            return None;
-        }
-        Some(self.stack[self.stack.len() - 1 - idx].clone())
-    }
-
-    /// return the number of values on the stack
-    fn len(&self) -> usize {
-        self.stack.len()
-    }
-
-    /// return if the stack is empty
-    fn is_empty(&self) -> bool {
-        self.stack.is_empty()
-    }
-}
-
+#[cfg(feature = "wat")]
 #[test]
 fn test_branch_wast() {
     let kvp = Kvp::default();
@@ -0,0 +1,97 @@
+// SPDX-License-Identifier: FSL-1.1
+#![cfg(feature = "wat")]
+mod common;
+
+use common::{context, load_wast, Kvp, Stk};
+use std::{fs, path::PathBuf};
+use wacc::{error::VmError, vm::{runtime::script_hash, Builder, DiskCache, Runtime}, Error};
+
+const KEY: &[u8] = b"for great justice, move every zig!";
+
+// the cache directories are private to each test so the tests can trust them
+fn cache_dir(name: &str) -> PathBuf {
+    let mut pb = std::env::temp_dir();
+    pb.push(format!("wacc-cache-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&pb);
+    pb
+}
+
+fn run_log(runtime: &Runtime) {
+    let kvp = Kvp::default();
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    let mut instance = Builder::new()
+        .with_runtime(runtime)
+        .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack))
+        .with_bytes(load_wast("log.wast"))
+        .try_build()
+        .unwrap();
+    assert!(instance.run("move_every_zig").unwrap().verdict);
+    assert_eq!(b"Hello World!\n".to_vec(), instance.log());
+}
+
+#[test]
+fn test_disk_cache_persists() {
+    let dir = cache_dir("persists");
+
+    // the first runtime compiles and stores the module
+    let runtime = unsafe { Runtime::builder().with_disk_cache(&dir, KEY) }.try_build().unwrap();
+    run_log(&runtime);
+    assert_eq!(1, fs::read_dir(&dir).unwrap().count());
+
+    // a restarted runtime loads it from the disk
+    let runtime = unsafe { Runtime::builder().with_disk_cache(&dir, KEY) }.try_build().unwrap();
+    let cache = DiskCache::new(&dir, KEY).unwrap();
+    let script = script_hash(&load_wast("log.wast")).unwrap();
+    assert!(unsafe { cache.get(&script, runtime.engine(), &runtime.options()) }.unwrap().is_some());
+    run_log(&runtime);
+
+    // a runtime with a different engine configuration gets its own entry
+    let runtime = unsafe { Runtime::builder().with_fuel().with_disk_cache(&dir, KEY) }.try_build().unwrap();
+    run_log(&runtime);
+    assert_eq!(2, fs::read_dir(&dir).unwrap().count());
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_disk_cache_corrupted() {
+    let dir = cache_dir("corrupted");
+    let runtime = unsafe { Runtime::builder().with_disk_cache(&dir, KEY) }.try_build().unwrap();
+    run_log(&runtime);
+
+    // flip a bit in the cached artifact
+    let cache = DiskCache::new(&dir, KEY).unwrap();
+    let script = script_hash(&load_wast("log.wast")).unwrap();
+    let path = cache.path(&script, runtime.engine(), &runtime.options());
+    let mut bytes = fs::read(&path).unwrap();
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0x01;
+    fs::write(&path, bytes).unwrap();
+
+    // the corruption is detected before deserializing
+    let result = unsafe { cache.get(&script, runtime.engine(), &runtime.options()) };
+    assert!(matches!(result, Err(Error::Vm(VmError::CorruptCache(_)))));
+
+    // a new runtime discards the entry and recompiles the script
+    let runtime = unsafe { Runtime::builder().with_disk_cache(&dir, KEY) }.try_build().unwrap();
+    run_log(&runtime);
+    assert!(unsafe { cache.get(&script, runtime.engine(), &runtime.options()) }.unwrap().is_some());
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_disk_cache_wrong_key() {
+    let dir = cache_dir("wrong-key");
+    let runtime = unsafe { Runtime::builder().with_disk_cache(&dir, KEY) }.try_build().unwrap();
+    run_log(&runtime);
+
+    // an entry written with another key isn't trusted
+    let cache = DiskCache::new(&dir, b"all your base are belong to us").unwrap();
+    let script = script_hash(&load_wast("log.wast")).unwrap();
+    let result = unsafe { cache.get(&script, runtime.engine(), &runtime.options()) };
+    assert!(matches!(result, Err(Error::Vm(VmError::CorruptCache(_)))));
+
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,112 @@
+// SPDX-License-Identifier: FSL-1.1
+//! The fixtures shared by the integration tests
+#![allow(dead_code)]
+use std::{collections::BTreeMap, fs::{read, read_to_string}, path::PathBuf};
+use wacc::{storage::{Pairs, Stack}, vm::{Context, Value}};
+
+/// Load a compiled script from the target directory
+pub fn load_wasm(file_name: &str) -> Vec<u8> {
+    let mut pb = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    pb.push("target");
+    pb.push(file_name);
+    println!("trying to load: {:?}", pb.as_os_str());
+    read(&pb).unwrap_or_else(|_| panic!("Error loading file {file_name}"))
+}
+
+/// Load a script in the text format from the examples
+pub fn load_wast(file_name: &str) -> Vec<u8> {
+    let mut pb = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    pb.push("examples");
+    pb.push("wast");
+    pb.push(file_name);
+    println!("trying to load: {:?}", pb.as_os_str());
+    read(&pb).unwrap_or_else(|_| panic!("Error loading file {file_name}"))
+}
+
+/// Load a script in the text format from the examples as a string
+pub fn load_wat(file_name: &str) -> String {
+    let mut pb = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    pb.push("examples");
+    pb.push("wast");
+    pb.push(file_name);
+    println!("trying to load: {:?}", pb.as_os_str());
+    read_to_string(&pb).unwrap_or_else(|_| panic!("Error loading file {file_name}"))
+}
+
+/// Build a context over the key-value pairs and stacks
+pub fn context<'a>(
+    current: &'a Kvp,
+    proposed: &'a Kvp,
+    pstack: &'a mut Stk,
+    rstack: &'a mut Stk,
+) -> Context<'a> {
+    Context::builder()
+        .with_current(current)
+        .with_proposed(proposed)
+        .with_pstack(pstack)
+        .with_rstack(rstack)
+        .try_build()
+        .unwrap()
+}
+
+#[derive(Default)]
+pub struct Kvp {
+    pub pairs: BTreeMap<String, Value>,
+}
+
+impl Pairs for Kvp {
+    /// get a value associated with the key
+    fn get(&self, key: &str) -> Option<Value> {
+        self.pairs.get(key).cloned()
+    }
+
+    /// add a key-value pair to the storage, return previous value if overwritten
+    fn put(&mut self, key: &str, value: &Value) -> Option<Value> {
+        self.pairs.insert(key.to_string(), value.clone())
+    }
+
+    /// get the keys that start with the prefix, in order
+    fn keys(&self, prefix: &str) -> Vec<String> {
+        self.pairs.keys().filter(|k| k.starts_with(prefix)).cloned().collect()
+    }
+}
+
+#[derive(Default)]
+pub struct Stk {
+    pub stack: Vec<Value>
+}
+
+impl Stack for Stk {
+    /// push a value onto the stack
+    fn push(&mut self, value: Value) {
+        self.stack.push(value);
+    }
+
+    /// remove the last top value from the stack
+    fn pop(&mut self) -> Option<Value> {
+        self.stack.pop()
+    }
+
+    /// get a reference to the top value on the stack
+    fn top(&self) -> Option<Value> {
+        self.stack.last().cloned()
+    }
+
+    /// peek at the item at the given index
+    fn peek(&self, idx: usize) -> Option<Value> {
+        if idx >= self.stack.len() {
+            return None;
+        }
+        Some(self.stack[self.stack.len() - 1 - idx].clone())
+    }
+
+    /// return the number of values on the stack
+    fn len(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// return if the stack is empty
+    fn is_empty(&self) -> bool {
+        self.stack.is_empty()
+    }
+}
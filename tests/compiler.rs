@@ -0,0 +1,90 @@
+// SPDX-License-Identifier: FSL-1.1
+#![cfg(feature = "wat")]
+mod common;
+
+use common::{context, load_wast, Kvp, Stk};
+use wacc::{error::VmError, vm::{Builder, Compiler, Runtime}, Error};
+
+#[test]
+fn test_precompiled() {
+    let artifact = Compiler::new()
+        .with_bytes(load_wast("log.wast"))
+        .try_compile()
+        .unwrap();
+
+    let kvp = Kvp::default();
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    // SAFETY: the artifact comes from the trusted Compiler above
+    let mut instance = unsafe {
+        Builder::new()
+            .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack))
+            .with_precompiled(&artifact)
+    }
+        .try_build()
+        .unwrap();
+    assert!(instance.run("move_every_zig").unwrap().verdict);
+    assert_eq!(b"Hello World!\n".to_vec(), instance.log());
+}
+
+#[test]
+fn test_precompiled_with_fuel() {
+    let artifact = Compiler::new()
+        .with_fuel(true)
+        .with_bytes(load_wast("log.wast"))
+        .try_compile()
+        .unwrap();
+
+    // loads with a fuel consuming runtime
+    let runtime = Runtime::builder().with_fuel().try_build().unwrap();
+    let kvp = Kvp::default();
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    // SAFETY: the artifact comes from the trusted Compiler above
+    let mut instance = unsafe {
+        Builder::new()
+            .with_runtime(&runtime)
+            .with_fuel(1_000_000)
+            .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack))
+            .with_precompiled(&artifact)
+    }
+        .try_build()
+        .unwrap();
+    assert!(instance.run("move_every_zig").unwrap().verdict);
+}
+
+#[test]
+fn test_precompiled_fuel_mismatch() {
+    let artifact = Compiler::new()
+        .with_fuel(true)
+        .with_bytes(load_wast("log.wast"))
+        .try_compile()
+        .unwrap();
+
+    let kvp = Kvp::default();
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    // SAFETY: the artifact comes from the trusted Compiler above
+    let result = unsafe {
+        Builder::new()
+            .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack))
+            .with_precompiled(&artifact)
+    }
+        .try_build();
+    assert!(matches!(result, Err(Error::Vm(VmError::IncompatibleArtifact(_)))));
+}
+
+#[test]
+fn test_precompiled_invalid() {
+    let kvp = Kvp::default();
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    // SAFETY: the script has no artifact header so it is rejected before deserializing
+    let result = unsafe {
+        Builder::new()
+            .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack))
+            .with_precompiled(load_wast("log.wast"))
+    }
+        .try_build();
+    assert!(matches!(result, Err(Error::Vm(VmError::InvalidArtifact(_)))));
+}
@@ -0,0 +1,72 @@
+// SPDX-License-Identifier: FSL-1.1
+mod common;
+
+use common::Kvp;
+use wacc::{error::VmError, vm::{Context, Value}, Error};
+
+#[test]
+fn test_context_defaults() {
+    let kvp = Kvp::default();
+    let mut pstack: Vec<Value> = Vec::default();
+    let mut rstack: Vec<Value> = Vec::default();
+    let context = Context::builder()
+        .with_current(&kvp)
+        .with_proposed(&kvp)
+        .with_pstack(&mut pstack)
+        .with_rstack(&mut rstack)
+        .try_build()
+        .unwrap();
+    assert_eq!("/", context.context);
+    assert_eq!(0, context.check_count());
+    assert!(context.logged().is_empty());
+    assert!(context.checks().is_empty());
+    assert!(context.access().current.is_empty());
+}
+
+#[test]
+fn test_context_missing_state() {
+    let kvp = Kvp::default();
+    let mut pstack: Vec<Value> = Vec::default();
+    let result = Context::builder()
+        .with_current(&kvp)
+        .with_proposed(&kvp)
+        .with_pstack(&mut pstack)
+        .try_build();
+    assert!(matches!(result, Err(Error::Vm(VmError::MissingStack))));
+
+    let mut pstack: Vec<Value> = Vec::default();
+    let mut rstack: Vec<Value> = Vec::default();
+    let result = Context::builder()
+        .with_current(&kvp)
+        .with_pstack(&mut pstack)
+        .with_rstack(&mut rstack)
+        .try_build();
+    assert!(matches!(result, Err(Error::Vm(VmError::MissingContext))));
+}
+
+#[test]
+fn test_context_key_path() {
+    let kvp = Kvp::default();
+    for (path, valid) in [
+        ("/", true),
+        ("/forks/child/", true),
+        ("", false),
+        ("forks/child/", false),
+        ("/forks/child", false),
+        ("/forks//child/", false),
+    ] {
+        let mut pstack: Vec<Value> = Vec::default();
+        let mut rstack: Vec<Value> = Vec::default();
+        let result = Context::builder()
+            .with_current(&kvp)
+            .with_proposed(&kvp)
+            .with_pstack(&mut pstack)
+            .with_rstack(&mut rstack)
+            .with_context(path)
+            .try_build();
+        match valid {
+            true => assert!(result.is_ok(), "{path}"),
+            false => assert!(matches!(result, Err(Error::Vm(VmError::InvalidKeyPath(_)))), "{path}"),
+        }
+    }
+}
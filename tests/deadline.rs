@@ -0,0 +1,181 @@
+// SPDX-License-Identifier: FSL-1.1
+#![cfg(feature = "wat")]
+mod common;
+
+use common::{context, load_wast, Kvp, Stk};
+use std::{thread, time::{Duration, Instant}};
+use wacc::{error::VmError, vm::{Builder, Runtime}, Error};
+
+#[test]
+fn test_deadline_exceeded() {
+    let runtime = Runtime::builder()
+        .with_epoch_tick(Duration::from_millis(1))
+        .try_build()
+        .unwrap();
+    let kvp = Kvp::default();
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    let mut instance = Builder::new()
+        .with_runtime(&runtime)
+        .with_deadline(Duration::from_millis(50))
+        .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack))
+        .with_bytes(load_wast("loop.wast"))
+        .try_build()
+        .unwrap();
+    let start = Instant::now();
+    let result = instance.run("move_every_zig");
+    assert!(matches!(result, Err(Error::Vm(VmError::Timeout(d))) if d == Duration::from_millis(50)));
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn test_deadline_not_reached() {
+    let runtime = Runtime::builder().with_deadlines().try_build().unwrap();
+    let kvp = Kvp::default();
+
+    // with and without a deadline
+    for deadline in [Some(Duration::from_secs(5)), None] {
+        let mut pstack = Stk::default();
+        let mut rstack = Stk::default();
+        let mut builder = Builder::new()
+            .with_runtime(&runtime)
+            .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack))
+            .with_bytes(load_wast("log.wast"));
+        if let Some(deadline) = deadline {
+            builder = builder.with_deadline(deadline);
+        }
+        let mut instance = builder.try_build().unwrap();
+        assert!(instance.run("move_every_zig").unwrap().verdict);
+        assert_eq!(b"Hello World!\n".to_vec(), instance.log());
+    }
+}
+
+#[test]
+fn test_deadline_requires_runtime() {
+    let kvp = Kvp::default();
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    let result = Builder::new()
+        .with_deadline(Duration::from_millis(50))
+        .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack))
+        .with_bytes(load_wast("log.wast"))
+        .try_build();
+    assert!(matches!(result, Err(Error::Vm(VmError::IncompatibleRuntime(_)))));
+
+    let runtime = Runtime::builder().try_build().unwrap();
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    let result = Builder::new()
+        .with_runtime(&runtime)
+        .with_deadline(Duration::from_millis(50))
+        .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack))
+        .with_bytes(load_wast("log.wast"))
+        .try_build();
+    assert!(matches!(result, Err(Error::Vm(VmError::IncompatibleRuntime(_)))));
+}
+
+#[test]
+fn test_interrupt() {
+    let runtime = Runtime::builder().with_deadlines().try_build().unwrap();
+    let kvp = Kvp::default();
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    let mut instance = Builder::new()
+        .with_runtime(&runtime)
+        .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack))
+        .with_bytes(load_wast("loop.wast"))
+        .try_build()
+        .unwrap();
+
+    // abort the script from another thread
+    let handle = instance.interrupt_handle().unwrap();
+    let canceller = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        handle.interrupt();
+    });
+    let result = instance.run("move_every_zig");
+    canceller.join().unwrap();
+    assert!(matches!(result, Err(Error::Vm(VmError::Cancelled))));
+}
+
+#[test]
+fn test_interrupt_before_run() {
+    let runtime = Runtime::builder().with_deadlines().try_build().unwrap();
+    let kvp = Kvp::default();
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    let mut instance = Builder::new()
+        .with_runtime(&runtime)
+        .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack))
+        .with_bytes(load_wast("loop.wast"))
+        .try_build()
+        .unwrap();
+    instance.interrupt_handle().unwrap().interrupt();
+    let result = instance.run("move_every_zig");
+    assert!(matches!(result, Err(Error::Vm(VmError::Cancelled))));
+}
+
+#[test]
+fn test_interrupt_is_cleared_after_run() {
+    let runtime = Runtime::builder().with_deadlines().try_build().unwrap();
+    let kvp = Kvp::default();
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    let mut instance = Builder::new()
+        .with_runtime(&runtime)
+        .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack))
+        .with_bytes(load_wast("log.wast"))
+        .try_build()
+        .unwrap();
+
+    // the interrupt only aborts the next run
+    instance.interrupt_handle().unwrap().interrupt();
+    let result = instance.run("move_every_zig");
+    assert!(matches!(result, Err(Error::Vm(VmError::Cancelled))));
+    assert!(instance.run("move_every_zig").unwrap().verdict);
+}
+
+#[test]
+fn test_interrupt_requires_epochs() {
+    let kvp = Kvp::default();
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    let instance = Builder::new()
+        .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack))
+        .with_bytes(load_wast("log.wast"))
+        .try_build()
+        .unwrap();
+    assert!(matches!(instance.interrupt_handle(), Err(Error::Vm(VmError::IncompatibleRuntime(_)))));
+}
+
+#[test]
+fn test_interrupt_is_per_instance() {
+    let runtime = Runtime::builder().with_deadlines().try_build().unwrap();
+    let kvp = Kvp::default();
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    let mut interrupted = Builder::new()
+        .with_runtime(&runtime)
+        .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack))
+        .with_bytes(load_wast("loop.wast"))
+        .try_build()
+        .unwrap();
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    let mut other = Builder::new()
+        .with_runtime(&runtime)
+        .with_deadline(Duration::from_secs(5))
+        .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack))
+        .with_bytes(load_wast("log.wast"))
+        .try_build()
+        .unwrap();
+
+    // interrupting one instance many times doesn't use up the deadline of another
+    let handle = interrupted.interrupt_handle().unwrap();
+    for _ in 0..10_000 {
+        handle.interrupt();
+    }
+    assert!(other.run("move_every_zig").unwrap().verdict);
+    let result = interrupted.run("move_every_zig");
+    assert!(matches!(result, Err(Error::Vm(VmError::Cancelled))));
+}
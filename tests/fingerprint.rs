@@ -0,0 +1,87 @@
+// SPDX-License-Identifier: FSL-1.1
+#![cfg(feature = "wat")]
+mod common;
+
+use common::{load_wast, Kvp, Stk};
+use multicodec::Codec;
+use multikey::{Multikey, Views};
+use wacc::{storage::{Pairs, Stack}, vm::{Builder, Context, Instance, Value}};
+use wasmtime::AsContextMut;
+
+const PUBKEY: &str = "3aed010874657374206b657901012084d515ef051e07d597f3c14ac09e5a9d5012c659c196d96db5c6b98ea552f603";
+
+fn test_example<'a>(
+    expected: bool,
+    current: &'a Kvp,
+    proposed: &'a Kvp,
+    pstack: &'a mut Stk,
+    rstack: &'a mut Stk,
+) -> Instance<'a> {
+    // build the context
+    let context = Context::builder()
+        .with_current(current)
+        .with_proposed(proposed)
+        .with_pstack(pstack)
+        .with_rstack(rstack)
+        .try_build()
+        .unwrap();
+
+    // construct the instance
+    let mut instance = Builder::new()
+        .with_context(context)
+        .with_bytes(load_wast("fingerprint_lock.wast"))
+        .try_build()
+        .unwrap();
+
+    // execute the instance
+    let result = instance.run("move_every_zig").unwrap().verdict;
+
+    assert_eq!(expected, result);
+    instance
+}
+
+fn check(kvp_lock: &Kvp, kvp_unlock: &Kvp, expected: bool) -> Option<Value> {
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+
+    let mut instance = test_example(expected, kvp_lock, kvp_unlock, &mut pstack, &mut rstack);
+    let mut ctx = instance.store.as_context_mut();
+    let context = ctx.data_mut();
+    assert_eq!(1, context.rstack.len());
+    context.rstack.top()
+}
+
+fn fingerprint(pubkey: &[u8], codec: Codec) -> Vec<u8> {
+    let mk = Multikey::try_from(pubkey).unwrap();
+    mk.fingerprint_view().unwrap().fingerprint(codec).unwrap().into()
+}
+
+#[test]
+fn test_fingerprint_matches() {
+    let pubkey = hex::decode(PUBKEY).unwrap();
+
+    let mut kvp_lock = Kvp::default();
+    let _ = kvp_lock.put("/fingerprint", &fingerprint(&pubkey, Codec::Sha2256).into());
+
+    let mut kvp_unlock = Kvp::default();
+    let _ = kvp_unlock.put("/entry/pubkey", &pubkey.into());
+
+    assert_eq!(check(&kvp_lock, &kvp_unlock, true), Some(Value::Success(0)));
+}
+
+#[test]
+fn test_fingerprint_mismatch() {
+    let pubkey = hex::decode(PUBKEY).unwrap();
+
+    // the fingerprint of some other data
+    let mut kvp_lock = Kvp::default();
+    let _ = kvp_lock.put("/fingerprint", &hex::decode("16206b761d3b2e7675e088e337a82207b55711d3957efdb877a3d261b0ca2c38e201").unwrap().into());
+
+    let mut kvp_unlock = Kvp::default();
+    let _ = kvp_unlock.put("/entry/pubkey", &pubkey.into());
+
+    assert_eq!(
+        check(&kvp_lock, &kvp_unlock, false),
+        Some(Value::Failure("fingerprint doesn't match".to_string()))
+    );
+}
@@ -0,0 +1,187 @@
+// SPDX-License-Identifier: FSL-1.1
+#![cfg(feature = "wat")]
+mod common;
+
+use common::{load_wast, Kvp, Stk};
+use wacc::{error::VmError, storage::Pairs, vm::{Builder, Context, CostSchedule, Fuel}, Error};
+
+fn context<'a>(
+    current: &'a Kvp,
+    proposed: &'a Kvp,
+    pstack: &'a mut Stk,
+    rstack: &'a mut Stk,
+    schedule: CostSchedule,
+) -> Context<'a> {
+    Context::builder()
+        .with_current(current)
+        .with_proposed(proposed)
+        .with_pstack(pstack)
+        .with_rstack(rstack)
+        .with_cost_schedule(schedule)
+        .try_build()
+        .unwrap()
+}
+
+#[test]
+fn test_fuel_report() {
+    let kvp = Kvp::default();
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    let mut instance = Builder::new()
+        .with_fuel(1_000_000)
+        .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack, CostSchedule::default()))
+        .with_bytes(load_wast("log.wast"))
+        .try_build()
+        .unwrap();
+
+    // nothing has been consumed before the run
+    let fuel = instance.fuel().unwrap();
+    assert_eq!(1_000_000, fuel.budget);
+    assert_eq!(0, fuel.consumed);
+
+    // the run result carries the fuel accounting
+    let result = instance.run("move_every_zig").unwrap();
+    assert!(result.verdict);
+    let fuel = result.fuel.unwrap();
+    assert_eq!(1_000_000, fuel.budget);
+    assert!(fuel.consumed > 0);
+    assert_eq!(fuel.budget, fuel.consumed + fuel.remaining);
+    assert_eq!(Some(fuel), instance.fuel());
+}
+
+#[test]
+fn test_fuel_no_report_without_fuel() {
+    let kvp = Kvp::default();
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    let mut instance = Builder::new()
+        .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack, CostSchedule::default()))
+        .with_bytes(load_wast("log.wast"))
+        .try_build()
+        .unwrap();
+    let result = instance.run("move_every_zig").unwrap();
+    assert!(result.verdict);
+    assert!(result.fuel.is_none());
+    assert!(instance.fuel().is_none());
+}
+
+#[test]
+fn test_out_of_fuel() {
+    let kvp = Kvp::default();
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    let mut instance = Builder::new()
+        .with_fuel(10_000)
+        .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack, CostSchedule::default()))
+        .with_bytes(load_wast("loop.wast"))
+        .try_build()
+        .unwrap();
+    let result = instance.run("move_every_zig");
+    assert!(matches!(result, Err(Error::Vm(VmError::OutOfFuel(10_000)))));
+    let fuel = instance.fuel().unwrap();
+    assert_eq!(0, fuel.remaining);
+    assert_eq!(10_000, fuel.consumed);
+}
+
+fn run_log(schedule: CostSchedule, budget: u64) -> Result<Fuel, Error> {
+    let kvp = Kvp::default();
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    let ctx = context(&kvp, &kvp, &mut pstack, &mut rstack, schedule);
+    let mut instance = Builder::new()
+        .with_fuel(budget)
+        .with_context(ctx)
+        .with_bytes(load_wast("log.wast"))
+        .try_build()?;
+    let result = instance.run("move_every_zig")?;
+    assert!(result.verdict);
+    Ok(result.fuel.unwrap())
+}
+
+#[test]
+fn test_host_function_costs() {
+    let free = run_log(CostSchedule::free(), 1_000_000).unwrap();
+    let schedule = CostSchedule {
+        log: 100,
+        string_byte: 3,
+        ..CostSchedule::free()
+    };
+    let charged = run_log(schedule, 1_000_000).unwrap();
+
+    // the log call plus the 12 bytes of "Hello World!" read from memory
+    assert_eq!(free.consumed + 100 + 3 * 12, charged.consumed);
+}
+
+#[test]
+fn test_host_function_out_of_fuel() {
+    let schedule = CostSchedule {
+        log: 1_000_000,
+        ..CostSchedule::default()
+    };
+    let result = run_log(schedule, 10_000);
+    assert!(matches!(result, Err(Error::Vm(VmError::OutOfFuel(10_000)))));
+}
+
+#[test]
+fn test_string_out_of_fuel() {
+    let schedule = CostSchedule {
+        string_byte: 1_000_000,
+        ..CostSchedule::default()
+    };
+    let kvp = Kvp::default();
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    let mut instance = Builder::new()
+        .with_fuel(10_000)
+        .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack, schedule))
+        .with_bytes(load_wast("log.wast"))
+        .try_build()
+        .unwrap();
+
+    // the host function traps before the string is read
+    let result = instance.run("move_every_zig");
+    assert!(matches!(result, Err(Error::Vm(VmError::OutOfFuel(10_000)))));
+    assert!(instance.log().is_empty());
+}
+
+fn run_nonce(schedule: CostSchedule, used: usize) -> Result<Fuel, Error> {
+    let mut kvp_lock = Kvp::default();
+    for i in 0..used {
+        let _ = kvp_lock.put(&format!("/nonces/{i}"), &format!("nonce {i}").into());
+    }
+    let mut kvp_unlock = Kvp::default();
+    let _ = kvp_unlock.put("/entry/nonce", &"fresh nonce".into());
+
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    let ctx = context(&kvp_lock, &kvp_unlock, &mut pstack, &mut rstack, schedule);
+    let mut instance = Builder::new()
+        .with_fuel(1_000_000)
+        .with_context(ctx)
+        .with_bytes(load_wast("nonce_lock.wast"))
+        .try_build()?;
+    let result = instance.run("move_every_zig")?;
+    assert!(result.verdict);
+    Ok(result.fuel.unwrap())
+}
+
+#[test]
+fn test_used_nonce_costs() {
+    let schedule = CostSchedule {
+        used_nonce: 7,
+        ..CostSchedule::free()
+    };
+
+    // every used nonce compared costs fuel
+    let none = run_nonce(schedule, 0).unwrap();
+    let three = run_nonce(schedule, 3).unwrap();
+    assert_eq!(none.consumed + 3 * 7, three.consumed);
+
+    // and the scan stops when the fuel runs out
+    let schedule = CostSchedule {
+        used_nonce: 400_000,
+        ..CostSchedule::free()
+    };
+    let result = run_nonce(schedule, 3);
+    assert!(matches!(result, Err(Error::Vm(VmError::OutOfFuel(1_000_000)))));
+}
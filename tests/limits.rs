@@ -0,0 +1,127 @@
+// SPDX-License-Identifier: FSL-1.1
+#![cfg(feature = "wat")]
+mod common;
+
+use common::{context, load_wast, Kvp, Stk};
+use wacc::{
+    error::VmError,
+    vm::{Builder, Compiler, Limits, Runtime},
+    Error,
+};
+
+fn exceeded(result: Result<(), Error>) -> Option<(String, u64, u64)> {
+    match result {
+        Err(Error::Vm(VmError::LimitExceeded { limit, value, max })) => Some((limit, value, max)),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_examples_within_default_limits() {
+    for script in ["lock.wast", "unlock.wast", "fork_lock.wast", "aggregate_lock.wast", "log.wast"] {
+        assert!(Limits::default().check(&load_wast(script)).is_ok(), "{script}");
+    }
+}
+
+#[test]
+fn test_module_size() {
+    let limits = Limits { module_size: 16, ..Limits::default() };
+    let (limit, _, max) = exceeded(limits.check(&load_wast("log.wast"))).unwrap();
+    assert_eq!("module size", limit);
+    assert_eq!(16, max);
+}
+
+#[test]
+fn test_memory_pages() {
+    let script = b"(module (memory (export \"memory\") 1 65536))";
+    assert_eq!(
+        Some(("memory pages".to_string(), 65536, 64)),
+        exceeded(Limits::default().check(script))
+    );
+    let script = b"(module (memory (export \"memory\") 128))";
+    assert_eq!(
+        Some(("memory pages".to_string(), 128, 64)),
+        exceeded(Limits::default().check(script))
+    );
+}
+
+#[test]
+fn test_table_size() {
+    let script = b"(module (table 10 100000 funcref))";
+    assert_eq!(
+        Some(("table size".to_string(), 100000, 1000)),
+        exceeded(Limits::default().check(script))
+    );
+}
+
+#[test]
+fn test_functions_and_data_segments() {
+    let script = br#"(module
+        (func $a) (func $b) (func $c)
+        (memory 1)
+        (data (i32.const 0) "a")
+        (data (i32.const 1) "b")
+    )"#;
+    let limits = Limits { functions: 2, ..Limits::default() };
+    assert_eq!(Some(("functions".to_string(), 3, 2)), exceeded(limits.check(script)));
+    let limits = Limits { data_segments: 1, ..Limits::default() };
+    assert_eq!(Some(("data segments".to_string(), 2, 1)), exceeded(limits.check(script)));
+    assert!(Limits::default().check(script).is_ok());
+}
+
+#[test]
+fn test_locals() {
+    let script = b"(module (func $f (local i32 i64 i32 i32)))";
+    let limits = Limits { locals: 3, ..Limits::default() };
+    assert_eq!(Some(("locals".to_string(), 4, 3)), exceeded(limits.check(script)));
+}
+
+#[test]
+fn test_compiler_checks_limits() {
+    let result = Compiler::new()
+        .with_limits(Limits { module_size: 16, ..Limits::default() })
+        .with_bytes(load_wast("log.wast"))
+        .try_compile();
+    assert!(matches!(result, Err(Error::Vm(VmError::LimitExceeded { .. }))));
+    assert!(Limits::unlimited().check(&load_wast("log.wast")).is_ok());
+}
+
+#[test]
+fn test_builder_checks_runtime_limits() {
+    let kvp = Kvp::default();
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    let small = Limits { module_size: 16, ..Limits::default() };
+
+    // the runtime limits apply when the builder doesn't set any
+    let runtime = Runtime::builder().with_limits(small).try_build().unwrap();
+    let result = Builder::new()
+        .with_runtime(&runtime)
+        .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack))
+        .with_bytes(load_wast("log.wast"))
+        .try_build()
+        .err();
+    assert!(matches!(result, Some(Error::Vm(VmError::LimitExceeded { .. }))));
+
+    // the builder limits apply even when the module is already cached
+    let runtime = Runtime::builder().try_build().unwrap();
+    assert!(runtime.module(&load_wast("log.wast")).is_ok());
+    let result = Builder::new()
+        .with_runtime(&runtime)
+        .with_limits(Limits { functions: 1, ..Limits::default() })
+        .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack))
+        .with_bytes(load_wast("log.wast"))
+        .try_build()
+        .err();
+    assert!(matches!(result, Some(Error::Vm(VmError::LimitExceeded { .. }))));
+
+    // the module is cached separately for each set of limits
+    let result = Builder::new()
+        .with_runtime(&runtime)
+        .with_limits(Limits::unlimited())
+        .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack))
+        .with_bytes(load_wast("log.wast"))
+        .try_build();
+    assert!(result.is_ok());
+    assert_eq!(2, runtime.cached());
+}
@@ -0,0 +1,87 @@
+// SPDX-License-Identifier: FSL-1.1
+#![cfg(feature = "wat")]
+mod common;
+
+use common::{load_wast, Kvp};
+use wacc::{storage::Pairs, vm::{Lock, Verifier}};
+
+const PREIMAGE_HASH: &str = "16206b761d3b2e7675e088e337a82207b55711d3957efdb877a3d261b0ca2c38e201";
+
+fn pairs(hash: &str) -> (Kvp, Kvp) {
+    let mut kvp_unlock = Kvp::default();
+    let _ = kvp_unlock.put("/entry/proof", &"for great justice, move every zig!".to_string().into());
+    let mut kvp_lock = Kvp::default();
+    let _ = kvp_lock.put("/hash", &hex::decode(hash).unwrap().into());
+    (kvp_lock, kvp_unlock)
+}
+
+#[test]
+fn test_lockset_precedence() {
+    let (kvp_lock, kvp_unlock) = pairs(PREIMAGE_HASH);
+    let script = load_wast("preimage_branch_lock.wast");
+    let locks = vec![
+        // the hash isn't under the child branch
+        Lock::new(&script, "move_every_zig").with_context("/child/"),
+        Lock::new(&script, "move_every_zig"),
+        Lock::new(load_wast("preimage_lock.wast"), "move_every_zig"),
+    ];
+    let verification = Verifier::new()
+        .with_unlock(load_wast("preimage_unlock.wast"), "for_great_justice")
+        .with_current(&kvp_lock)
+        .with_proposed(&kvp_unlock)
+        .verify_locks(&locks)
+        .unwrap();
+    assert!(verification.verdict());
+
+    // the second lock satisfied the update and the third wasn't run
+    let satisfied = verification.satisfied.unwrap();
+    assert_eq!(1, satisfied.index);
+    let check = satisfied.check.unwrap();
+    assert_eq!("check_preimage", check.function);
+    assert_eq!(vec!["/hash".to_string()], check.keys);
+    assert_eq!(2, verification.locks.len());
+    assert!(!verification.locks[0].verdict());
+    assert_eq!(vec!["/child/hash".to_string()], verification.locks[0].outcome().unwrap().checks[0].keys);
+}
+
+#[test]
+fn test_lockset_unsatisfied() {
+    let (kvp_lock, kvp_unlock) = pairs("16200000000000000000000000000000000000000000000000000000000000000000");
+    let locks = vec![
+        Lock::new(load_wast("preimage_branch_lock.wast"), "move_every_zig"),
+        Lock::new(load_wast("preimage_lock.wast"), "move_every_zig"),
+    ];
+    let verification = Verifier::new()
+        .with_unlock(load_wast("preimage_unlock.wast"), "for_great_justice")
+        .with_current(&kvp_lock)
+        .with_proposed(&kvp_unlock)
+        .verify_locks(&locks)
+        .unwrap();
+    assert!(!verification.verdict());
+    assert!(verification.satisfied.is_none());
+    assert_eq!(2, verification.locks.len());
+}
+
+#[test]
+fn test_lockset_failed_lock() {
+    let (kvp_lock, kvp_unlock) = pairs(PREIMAGE_HASH);
+    let locks = vec![
+        // the lock script doesn't compile
+        Lock::new(b"not a script", "move_every_zig"),
+        // the entry point doesn't exist
+        Lock::new(load_wast("preimage_lock.wast"), "missing"),
+        Lock::new(load_wast("preimage_lock.wast"), "move_every_zig"),
+    ];
+    let verification = Verifier::new()
+        .with_unlock(load_wast("preimage_unlock.wast"), "for_great_justice")
+        .with_current(&kvp_lock)
+        .with_proposed(&kvp_unlock)
+        .verify_locks(&locks)
+        .unwrap();
+    assert!(verification.verdict());
+    assert_eq!(2, verification.satisfied.unwrap().index);
+    assert_eq!(3, verification.locks.len());
+    assert!(verification.locks[0].error().is_some());
+    assert!(verification.locks[1].error().is_some());
+    assert!(verification.locks[2].verdict());
+}
@@ -0,0 +1,47 @@
+// SPDX-License-Identifier: FSL-1.1
+#![cfg(not(feature = "wat"))]
+mod common;
+
+use common::{context, Kvp, Stk};
+use wacc::{
+    error::VmError,
+    vm::{Builder, Limits},
+    Error,
+};
+
+/// A script in the binary format that exports move_every_zig returning 1
+const SCRIPT: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // magic and version
+    0x01, 0x05, 0x01, 0x60, 0x00, 0x01, 0x7f, // type section: () -> i32
+    0x03, 0x02, 0x01, 0x00, // function section
+    0x07, 0x12, 0x01, 0x0e, b'm', b'o', b'v', b'e', b'_', b'e', b'v', b'e', b'r', b'y', b'_', b'z', b'i', b'g',
+    0x00, 0x00, // export section
+    0x0a, 0x06, 0x01, 0x04, 0x00, 0x41, 0x01, 0x0b, // code section: i32.const 1
+];
+
+#[test]
+fn test_text_unsupported() {
+    let kvp = Kvp::default();
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    let result = Builder::new()
+        .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack))
+        .with_bytes(b"(module)")
+        .try_build()
+        .err();
+    assert!(matches!(result, Some(Error::Vm(VmError::TextUnsupported))));
+    assert!(matches!(Limits::default().check(b"(module)"), Err(Error::Vm(VmError::TextUnsupported))));
+}
+
+#[test]
+fn test_binary_supported() {
+    let kvp = Kvp::default();
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    let mut instance = Builder::new()
+        .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack))
+        .with_bytes(SCRIPT)
+        .try_build()
+        .unwrap();
+    assert!(instance.run("move_every_zig").unwrap().verdict);
+}
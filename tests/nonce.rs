@@ -0,0 +1,110 @@
+// SPDX-License-Identifier: FSL-1.1
+#![cfg(feature = "wat")]
+mod common;
+
+use common::{load_wast, Kvp, Stk};
+use wacc::{storage::{Pairs, Stack}, vm::{Builder, Context, Instance, Value}};
+use wasmtime::AsContextMut;
+
+fn test_example<'a>(
+    expected: bool,
+    current: &'a Kvp,
+    proposed: &'a Kvp,
+    pstack: &'a mut Stk,
+    rstack: &'a mut Stk,
+) -> Instance<'a> {
+    // build the context
+    let context = Context::builder()
+        .with_current(current)
+        .with_proposed(proposed)
+        .with_pstack(pstack)
+        .with_rstack(rstack)
+        .try_build()
+        .unwrap();
+
+    // construct the instance
+    let mut instance = Builder::new()
+        .with_context(context)
+        .with_bytes(load_wast("nonce_lock.wast"))
+        .try_build()
+        .unwrap();
+
+    // execute the instance
+    let result = instance.run("move_every_zig").unwrap().verdict;
+
+    assert_eq!(expected, result);
+    instance
+}
+
+fn check(kvp_lock: &Kvp, kvp_unlock: &Kvp, expected: bool) -> Option<Value> {
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+
+    let mut instance = test_example(expected, kvp_lock, kvp_unlock, &mut pstack, &mut rstack);
+    let mut ctx = instance.store.as_context_mut();
+    let context = ctx.data_mut();
+    assert_eq!(1, context.rstack.len());
+    context.rstack.top()
+}
+
+#[test]
+fn test_fresh_nonce() {
+    let mut kvp_lock = Kvp::default();
+    let _ = kvp_lock.put("/nonces/0", &b"nonce 0".as_slice().into());
+    let _ = kvp_lock.put("/nonces/1", &b"nonce 1".as_slice().into());
+
+    let mut kvp_unlock = Kvp::default();
+    let _ = kvp_unlock.put("/entry/nonce", &b"nonce 2".as_slice().into());
+
+    assert_eq!(check(&kvp_lock, &kvp_unlock, true), Some(Value::Success(0)));
+}
+
+#[test]
+fn test_replayed_nonce() {
+    let mut kvp_lock = Kvp::default();
+    let _ = kvp_lock.put("/nonces/0", &b"nonce 0".as_slice().into());
+    let _ = kvp_lock.put("/nonces/1", &b"nonce 1".as_slice().into());
+    // a nonce outside of the used prefix doesn't count
+    let _ = kvp_lock.put("/other/0", &b"nonce 2".as_slice().into());
+
+    let mut kvp_unlock = Kvp::default();
+    let _ = kvp_unlock.put("/entry/nonce", &b"nonce 1".as_slice().into());
+
+    assert_eq!(
+        check(&kvp_lock, &kvp_unlock, false),
+        Some(Value::Failure("nonce already used at /nonces/1".to_string()))
+    );
+}
+
+#[test]
+fn test_replayed_nonce_different_hint() {
+    let mut kvp_lock = Kvp::default();
+    let _ = kvp_lock.put("/nonces/0", &b"nonce 0".as_slice().into());
+
+    // the same bytes with another hint, or as a string, are still a replay
+    let mut kvp_unlock = Kvp::default();
+    let replay = Value::Bin { hint: "fresh".to_string(), data: b"nonce 0".to_vec() };
+    let _ = kvp_unlock.put("/entry/nonce", &replay);
+    assert_eq!(
+        check(&kvp_lock, &kvp_unlock, false),
+        Some(Value::Failure("nonce already used at /nonces/0".to_string()))
+    );
+
+    let mut kvp_unlock = Kvp::default();
+    let _ = kvp_unlock.put("/entry/nonce", &"nonce 0".into());
+    assert_eq!(
+        check(&kvp_lock, &kvp_unlock, false),
+        Some(Value::Failure("nonce already used at /nonces/0".to_string()))
+    );
+}
+
+#[test]
+fn test_missing_nonce() {
+    let kvp_lock = Kvp::default();
+    let kvp_unlock = Kvp::default();
+
+    assert_eq!(
+        check(&kvp_lock, &kvp_unlock, false),
+        Some(Value::Failure("no nonce associated with /entry/nonce".to_string()))
+    );
+}
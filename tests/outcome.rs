@@ -0,0 +1,94 @@
+// SPDX-License-Identifier: FSL-1.1
+#![cfg(feature = "wat")]
+mod common;
+
+use common::{context, load_wast, Kvp, Stk};
+use std::collections::{BTreeMap, BTreeSet};
+use wacc::{storage::Pairs, vm::{AccessSet, Builder, Check, RunOutcome}};
+
+fn preimage_outcome(lock: &str, hash: &str) -> RunOutcome {
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    let mut kvp_unlock = Kvp::default();
+    let _ = kvp_unlock.put("/entry/proof", &"for great justice, move every zig!".to_string().into());
+    let mut kvp_lock = Kvp::default();
+    let _ = kvp_lock.put("/hash", &hex::decode(hash).unwrap().into());
+
+    { // unlock
+        let mut instance = Builder::new()
+            .with_context(context(&kvp_unlock, &kvp_unlock, &mut pstack, &mut rstack))
+            .with_bytes(load_wast("preimage_unlock.wast"))
+            .try_build()
+            .unwrap();
+        let outcome = instance.execute("for_great_justice").unwrap();
+        assert!(outcome.verdict);
+        assert!(outcome.checks.is_empty());
+        assert_eq!(1, outcome.pstack_len);
+    }
+
+    // lock
+    let mut instance = Builder::new()
+        .with_fuel(1_000_000)
+        .with_context(context(&kvp_lock, &kvp_unlock, &mut pstack, &mut rstack))
+        .with_bytes(load_wast(lock))
+        .try_build()
+        .unwrap();
+    instance.execute("move_every_zig").unwrap()
+}
+
+#[test]
+fn test_outcome_success() {
+    let outcome = preimage_outcome("preimage_lock.wast", "16206b761d3b2e7675e088e337a82207b55711d3957efdb877a3d261b0ca2c38e201");
+    assert!(outcome.verdict);
+    assert_eq!(
+        vec![Check {
+            function: "check_preimage".to_string(),
+            keys: vec!["/hash".to_string()],
+            passed: true,
+            reason: None,
+        }],
+        outcome.checks
+    );
+    assert_eq!(0, outcome.failures().count());
+    assert_eq!(0, outcome.pstack_len);
+    assert_eq!(1, outcome.rstack_len);
+    assert!(outcome.fuel.unwrap().consumed > 0);
+    assert!(outcome.log.is_empty());
+}
+
+#[test]
+fn test_outcome_failure() {
+    // the hash of a different preimage
+    let outcome = preimage_outcome("preimage_lock.wast", "16200000000000000000000000000000000000000000000000000000000000000000");
+    assert!(!outcome.verdict);
+    let failures: Vec<&Check> = outcome.failures().collect();
+    assert_eq!(1, failures.len());
+    assert_eq!("check_preimage", failures[0].function);
+    assert_eq!(vec!["/hash".to_string()], failures[0].keys);
+    assert_eq!(Some("preimage doesn't match".to_string()), failures[0].reason);
+    // the preimage is left on the stack when the check fails
+    assert_eq!(1, outcome.pstack_len);
+}
+
+#[test]
+fn test_outcome_access() {
+    let hash = "16206b761d3b2e7675e088e337a82207b55711d3957efdb877a3d261b0ca2c38e201";
+    let outcome = preimage_outcome("preimage_lock.wast", hash);
+    assert_eq!(
+        AccessSet {
+            current: BTreeSet::from(["/hash".to_string()]),
+            ..Default::default()
+        },
+        outcome.access
+    );
+
+    // the branched key-path is recorded along with the key read
+    let outcome = preimage_outcome("preimage_branch_lock.wast", hash);
+    assert!(outcome.verdict);
+    assert_eq!(BTreeSet::from(["/hash".to_string()]), outcome.access.current);
+    assert!(outcome.access.proposed.is_empty());
+    assert_eq!(
+        BTreeMap::from([("hash".to_string(), "/hash".to_string())]),
+        outcome.access.branches
+    );
+}
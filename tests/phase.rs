@@ -0,0 +1,70 @@
+// SPDX-License-Identifier: FSL-1.1
+#![cfg(feature = "wat")]
+mod common;
+
+use common::{context, load_wast, Kvp, Stk};
+use wacc::{error::VmError, storage::Pairs, vm::{Builder, Phase, Verifier}, Error};
+
+fn build(phase: Phase, script: &str) -> Result<(), Error> {
+    let kvp = Kvp::default();
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    Builder::new()
+        .with_phase(phase)
+        .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack))
+        .with_bytes(load_wast(script))
+        .try_build()
+        .map(|_| ())
+}
+
+#[test]
+fn test_phase_allows() {
+    for phase in [Phase::Any, Phase::Unlock] {
+        assert!(build(phase, "preimage_unlock.wast").is_ok());
+    }
+    for phase in [Phase::Any, Phase::Lock] {
+        assert!(build(phase, "preimage_lock.wast").is_ok());
+    }
+    // branch and log are available in every phase
+    for phase in [Phase::Any, Phase::Unlock, Phase::Lock] {
+        assert!(build(phase, "branch.wast").is_ok());
+    }
+}
+
+#[test]
+fn test_unlock_cannot_check() {
+    match build(Phase::Unlock, "pubkeysig_lock.wast") {
+        Err(Error::Vm(VmError::UnavailableImport { phase, name })) => {
+            assert_eq!(Phase::Unlock, phase);
+            assert_eq!("_check_signature", name);
+        }
+        _ => panic!("expected an unavailable import error"),
+    }
+}
+
+#[test]
+fn test_lock_cannot_stage() {
+    match build(Phase::Lock, "preimage_unlock.wast") {
+        Err(Error::Vm(VmError::UnavailableImport { phase, name })) => {
+            assert_eq!(Phase::Lock, phase);
+            assert_eq!("_push", name);
+        }
+        _ => panic!("expected an unavailable import error"),
+    }
+}
+
+#[test]
+fn test_verifier_phases() {
+    let mut kvp_unlock = Kvp::default();
+    let _ = kvp_unlock.put("/entry/proof", &"for great justice, move every zig!".to_string().into());
+    let kvp_lock = Kvp::default();
+
+    // the scripts are swapped
+    let result = Verifier::new()
+        .with_unlock(load_wast("preimage_lock.wast"), "move_every_zig")
+        .with_lock(load_wast("preimage_unlock.wast"), "for_great_justice")
+        .with_current(&kvp_lock)
+        .with_proposed(&kvp_unlock)
+        .verify();
+    assert!(matches!(result, Err(Error::Vm(VmError::UnavailableImport { phase: Phase::Unlock, .. }))));
+}
@@ -0,0 +1,122 @@
+// SPDX-License-Identifier: FSL-1.1
+#![cfg(feature = "wat")]
+mod common;
+
+use common::{load_wast, Kvp, Stk};
+use multicodec::Codec;
+use wacc::{storage::{Pairs, Stack}, vm::{Builder, Context, Instance, SignaturePolicy, Value}};
+use wasmtime::AsContextMut;
+
+const PUBKEY: &str = "3aed010874657374206b657901012084d515ef051e07d597f3c14ac09e5a9d5012c659c196d96db5c6b98ea552f603";
+const SIGNATURE: &str = "3983a6c0060001004076fee92ca796162b5e37a84b4150da685d636491b43c1e2a1fab392a7337553502588a609075b56c46b5c033b260d8d314b584e396fc2221c55f54843679ee08";
+
+fn test_example<'a>(
+    policy: SignaturePolicy,
+    expected: bool,
+    current: &'a Kvp,
+    proposed: &'a Kvp,
+    pstack: &'a mut Stk,
+    rstack: &'a mut Stk,
+) -> Instance<'a> {
+    // build the context
+    let context = Context::builder()
+        .with_current(current)
+        .with_proposed(proposed)
+        .with_pstack(pstack)
+        .with_rstack(rstack)
+        .with_policy(policy)
+        .try_build()
+        .unwrap();
+
+    // construct the instance
+    let mut instance = Builder::new()
+        .with_context(context)
+        .with_bytes(load_wast("pubkeysig_lock.wast"))
+        .try_build()
+        .unwrap();
+
+    // execute the instance
+    let result = instance.run("move_every_zig").unwrap().verdict;
+
+    assert_eq!(expected, result);
+    instance
+}
+
+fn setup() -> (Kvp, Kvp, Stk) {
+    // the key-value pair store with the signed message
+    let mut kvp_unlock = Kvp::default();
+    let _ = kvp_unlock.put("/entry/", &"for great justice, move every zig!".to_string().into());
+
+    // the key-value pair store with the encoded Multikey
+    let mut kvp_lock = Kvp::default();
+    let _ = kvp_lock.put("/pubkey", &hex::decode(PUBKEY).unwrap().into());
+
+    // the parameter stack as the unlock script would leave it
+    let mut pstack = Stk::default();
+    pstack.push(hex::decode(SIGNATURE).unwrap().into());
+
+    (kvp_unlock, kvp_lock, pstack)
+}
+
+#[test]
+fn test_policy_allows() {
+    let (kvp_unlock, kvp_lock, mut pstack) = setup();
+    let mut rstack = Stk::default();
+    let policy = SignaturePolicy::new()
+        .allow_key(Codec::Ed25519Pub)
+        .allow_sig(Codec::EddsaMsig)
+        .with_min_security_level(128);
+
+    let mut instance = test_example(policy, true, &kvp_lock, &kvp_unlock, &mut pstack, &mut rstack);
+    let mut ctx = instance.store.as_context_mut();
+    let context = ctx.data_mut();
+    assert_eq!(0, context.pstack.len());
+    assert_eq!(context.rstack.top(), Some(Value::Success(0)));
+}
+
+#[test]
+fn test_policy_denies_key_codec() {
+    let (kvp_unlock, kvp_lock, mut pstack) = setup();
+    let mut rstack = Stk::default();
+    let policy = SignaturePolicy::new().deny_key(Codec::Ed25519Pub);
+
+    let mut instance = test_example(policy, false, &kvp_lock, &kvp_unlock, &mut pstack, &mut rstack);
+    let mut ctx = instance.store.as_context_mut();
+    let context = ctx.data_mut();
+    // the signature is left on the stack because it was never verified
+    assert_eq!(1, context.pstack.len());
+    match context.rstack.top() {
+        Some(Value::Failure(reason)) => assert!(reason.starts_with("signature policy violation")),
+        v => panic!("unexpected return value {v:?}"),
+    }
+}
+
+#[test]
+fn test_policy_denies_sig_codec() {
+    let (kvp_unlock, kvp_lock, mut pstack) = setup();
+    let mut rstack = Stk::default();
+    let policy = SignaturePolicy::new().allow_sig(Codec::Es256KMsig);
+
+    let mut instance = test_example(policy, false, &kvp_lock, &kvp_unlock, &mut pstack, &mut rstack);
+    let mut ctx = instance.store.as_context_mut();
+    let context = ctx.data_mut();
+    match context.rstack.top() {
+        Some(Value::Failure(reason)) => assert!(reason.starts_with("signature policy violation")),
+        v => panic!("unexpected return value {v:?}"),
+    }
+}
+
+#[test]
+fn test_policy_min_security_level() {
+    let (kvp_unlock, kvp_lock, mut pstack) = setup();
+    let mut rstack = Stk::default();
+    let policy = SignaturePolicy::new().with_min_security_level(192);
+
+    let mut instance = test_example(policy, false, &kvp_lock, &kvp_unlock, &mut pstack, &mut rstack);
+    let mut ctx = instance.store.as_context_mut();
+    let context = ctx.data_mut();
+    match context.rstack.top() {
+        Some(Value::Failure(reason)) => assert!(reason.starts_with("signature policy violation")),
+        v => panic!("unexpected return value {v:?}"),
+    }
+}
@@ -0,0 +1,105 @@
+// SPDX-License-Identifier: FSL-1.1
+#![cfg(feature = "wat")]
+mod common;
+
+use common::{context, load_wast, Kvp, Stk};
+use wacc::{error::VmError, vm::{Builder, Compiler, Profile, Runtime}, Error};
+
+const DETERMINISTIC: Profile = Profile::Deterministic { reject_floats: true };
+
+#[test]
+fn test_deterministic_rejects_floats() {
+    let kvp = Kvp::default();
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    let result = Builder::new()
+        .with_profile(DETERMINISTIC)
+        .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack))
+        .with_bytes(load_wast("float.wast"))
+        .try_build();
+    assert!(matches!(result, Err(Error::Vm(VmError::NonDeterministic(_)))));
+
+    // the compiler applies the same rule
+    let result = Compiler::new()
+        .with_profile(DETERMINISTIC)
+        .with_bytes(load_wast("float.wast"))
+        .try_compile();
+    assert!(matches!(result, Err(Error::Vm(VmError::NonDeterministic(_)))));
+}
+
+#[test]
+fn test_deterministic_allows_floats() {
+    let kvp = Kvp::default();
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    let mut instance = Builder::new()
+        .with_profile(Profile::Deterministic { reject_floats: false })
+        .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack))
+        .with_bytes(load_wast("float.wast"))
+        .try_build()
+        .unwrap();
+    assert!(instance.run("move_every_zig").unwrap().verdict);
+}
+
+#[test]
+fn test_deterministic_precompiled() {
+    let artifact = Compiler::new()
+        .with_profile(DETERMINISTIC)
+        .with_bytes(load_wast("log.wast"))
+        .try_compile()
+        .unwrap();
+
+    // loads with the same profile
+    let kvp = Kvp::default();
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    // SAFETY: the artifact comes from the trusted Compiler above
+    let mut instance = unsafe {
+        Builder::new()
+            .with_profile(DETERMINISTIC)
+            .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack))
+            .with_precompiled(&artifact)
+    }
+        .try_build()
+        .unwrap();
+    assert!(instance.run("move_every_zig").unwrap().verdict);
+
+    // and is rejected by the default profile
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    // SAFETY: the artifact comes from the trusted Compiler above
+    let result = unsafe {
+        Builder::new()
+            .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack))
+            .with_precompiled(&artifact)
+    }
+        .try_build();
+    assert!(matches!(result, Err(Error::Vm(VmError::IncompatibleArtifact(_)))));
+}
+
+#[test]
+fn test_deterministic_runtime() {
+    let runtime = Runtime::builder().with_profile(DETERMINISTIC).try_build().unwrap();
+    let kvp = Kvp::default();
+
+    // the runtime validates scripts against its profile
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    let result = Builder::new()
+        .with_runtime(&runtime)
+        .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack))
+        .with_bytes(load_wast("float.wast"))
+        .try_build();
+    assert!(matches!(result, Err(Error::Vm(VmError::NonDeterministic(_)))));
+
+    // and a builder can't ask for a different profile
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    let result = Builder::new()
+        .with_runtime(&runtime)
+        .with_profile(Profile::Default)
+        .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack))
+        .with_bytes(load_wast("log.wast"))
+        .try_build();
+    assert!(matches!(result, Err(Error::Vm(VmError::IncompatibleRuntime(_)))));
+}
@@ -0,0 +1,76 @@
+// SPDX-License-Identifier: FSL-1.1
+#![cfg(feature = "wat")]
+mod common;
+
+use common::{context, load_wast, Kvp, Stk};
+use wacc::{error::VmError, vm::{Builder, Runtime}, Error};
+
+#[test]
+fn test_runtime_module_cache() {
+    let runtime = Runtime::builder().with_fuel().try_build().unwrap();
+    let script = load_wast("log.wast");
+    let kvp = Kvp::default();
+
+    for _ in 0..3 {
+        let mut pstack = Stk::default();
+        let mut rstack = Stk::default();
+        let mut instance = Builder::new()
+            .with_runtime(&runtime)
+            .with_fuel(1_000_000)
+            .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack))
+            .with_bytes(&script)
+            .try_build()
+            .unwrap();
+        assert!(instance.run("move_every_zig").unwrap().verdict);
+        assert_eq!(b"Hello World!\n".to_vec(), instance.log());
+    }
+
+    // the script was only compiled once
+    assert_eq!(1, runtime.cached());
+
+    // a different script gets its own entry
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    let mut instance = Builder::new()
+        .with_runtime(&runtime)
+        .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack))
+        .with_bytes(load_wast("branch.wast"))
+        .try_build()
+        .unwrap();
+    assert!(instance.run("move_every_zig").unwrap().verdict);
+    assert_eq!(2, runtime.cached());
+}
+
+#[test]
+fn test_runtime_cache_eviction() {
+    let runtime = Runtime::builder().with_cache_size(1).try_build().unwrap();
+    let kvp = Kvp::default();
+
+    for script in ["log.wast", "branch.wast"] {
+        let mut pstack = Stk::default();
+        let mut rstack = Stk::default();
+        let mut instance = Builder::new()
+            .with_runtime(&runtime)
+            .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack))
+            .with_bytes(load_wast(script))
+            .try_build()
+            .unwrap();
+        assert!(instance.run("move_every_zig").unwrap().verdict);
+    }
+    assert_eq!(1, runtime.cached());
+}
+
+#[test]
+fn test_runtime_without_fuel() {
+    let runtime = Runtime::builder().try_build().unwrap();
+    let kvp = Kvp::default();
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    let result = Builder::new()
+        .with_runtime(&runtime)
+        .with_fuel(1_000_000)
+        .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack))
+        .with_bytes(load_wast("log.wast"))
+        .try_build();
+    assert!(matches!(result, Err(Error::Vm(VmError::IncompatibleRuntime(_)))));
+}
@@ -0,0 +1,124 @@
+// SPDX-License-Identifier: FSL-1.1
+#![cfg(feature = "wat")]
+mod common;
+
+use common::{load_wast, Kvp, Stk};
+use multicodec::Codec;
+use multikey::{mk, Multikey, Views};
+use wacc::{storage::{Pairs, Stack}, vm::{Builder, Context, Instance, SignedMessage, Value}};
+use wasmtime::AsContextMut;
+
+const MESSAGE: &[u8] = b"for great justice, move every zig!";
+
+fn test_example<'a>(
+    script: Vec<u8>,
+    expected: bool,
+    context: &str,
+    current: &'a Kvp,
+    proposed: &'a Kvp,
+    pstack: &'a mut Stk,
+    rstack: &'a mut Stk,
+) -> Instance<'a> {
+    // build the context
+    let context = Context::builder()
+        .with_current(current)
+        .with_proposed(proposed)
+        .with_pstack(pstack)
+        .with_rstack(rstack)
+        .with_context(context)
+        .try_build()
+        .unwrap();
+
+    // construct the instance
+    let mut instance = Builder::new()
+        .with_context(context)
+        .with_bytes(&script)
+        .try_build()
+        .unwrap();
+
+    // execute the instance
+    let result = instance.run("move_every_zig").unwrap().verdict;
+
+    assert_eq!(expected, result);
+    instance
+}
+
+fn secret_key(codec: Codec) -> Multikey {
+    let mut rng = rand::rngs::OsRng;
+    mk::Builder::new_from_random_bytes(codec, &mut rng)
+        .unwrap()
+        .try_build()
+        .unwrap()
+}
+
+fn public_key(sk: &Multikey) -> Vec<u8> {
+    sk.conv_view().unwrap().to_public_key().unwrap().into()
+}
+
+fn sign(sk: &Multikey, msg: &[u8]) -> Vec<u8> {
+    sk.sign_view().unwrap().sign(msg, false, None).unwrap().into()
+}
+
+fn check(script: &str, context: &str, sk: &Multikey, sig: Vec<u8>, expected: bool) {
+    // the key-value pair store with the signed message
+    let mut kvp_unlock = Kvp::default();
+    let _ = kvp_unlock.put("/entry/", &MESSAGE.into());
+
+    // the key-value pair store with the encoded Multikey
+    let mut kvp_lock = Kvp::default();
+    let _ = kvp_lock.put("/pubkey", &public_key(sk).into());
+
+    // the parameter stack as the unlock script would leave it
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    pstack.push(sig.into());
+
+    let mut instance = test_example(load_wast(script), expected, context, &kvp_lock, &kvp_unlock, &mut pstack, &mut rstack);
+    let mut ctx = instance.store.as_context_mut();
+    let context = ctx.data_mut();
+    assert_eq!(1, context.rstack.len());
+    if expected {
+        assert_eq!(0, context.pstack.len());
+        assert_eq!(context.rstack.top(), Some(Value::Success(0)));
+    } else {
+        assert_eq!(1, context.pstack.len());
+    }
+}
+
+fn test_hashed(codec: Codec) {
+    let sk = secret_key(codec);
+    let hashed = SignedMessage::Hashed(Codec::Sha2256).encode("/", "/entry/", MESSAGE).unwrap();
+    check("hashed_sig_lock.wast", "/", &sk, sign(&sk, &hashed), true);
+    // a signature over the raw message does not verify as a pre-hashed one
+    check("hashed_sig_lock.wast", "/", &sk, sign(&sk, MESSAGE), false);
+}
+
+fn test_domain(codec: Codec) {
+    let sk = secret_key(codec);
+    let msg = SignedMessage::Domain.encode("/forks/child/", "/entry/", MESSAGE).unwrap();
+    check("domain_sig_lock.wast", "/forks/child/", &sk, sign(&sk, &msg), true);
+    // the same signature cannot be replayed in another context
+    check("domain_sig_lock.wast", "/forks/other/", &sk, sign(&sk, &msg), false);
+    // nor is a signature over the raw message accepted
+    check("domain_sig_lock.wast", "/forks/child/", &sk, sign(&sk, MESSAGE), false);
+}
+
+#[test]
+fn test_hashed_ed25519() {
+    test_hashed(Codec::Ed25519Priv);
+}
+
+#[test]
+fn test_hashed_secp256k1() {
+    test_hashed(Codec::Secp256K1Priv);
+}
+
+#[test]
+fn test_domain_ed25519() {
+    test_domain(Codec::Ed25519Priv);
+}
+
+#[test]
+fn test_domain_secp256k1() {
+    test_domain(Codec::Secp256K1Priv);
+}
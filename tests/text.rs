@@ -0,0 +1,64 @@
+// SPDX-License-Identifier: FSL-1.1
+#![cfg(feature = "wat")]
+mod common;
+
+use common::{context, load_wat, Kvp, Stk};
+use wacc::{error::VmError, vm::Builder, Error};
+
+#[test]
+fn test_with_wat() {
+    let kvp = Kvp::default();
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    let mut instance = Builder::new()
+        .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack))
+        .with_wat(load_wat("log.wast"))
+        .try_build()
+        .unwrap();
+    assert!(instance.run("move_every_zig").unwrap().verdict);
+    assert_eq!(b"Hello World!\n".to_vec(), instance.log());
+}
+
+#[test]
+fn test_with_wasm() {
+    let kvp = Kvp::default();
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    let wasm = wat::parse_str(load_wat("log.wast")).unwrap();
+    let mut instance = Builder::new()
+        .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack))
+        .with_wasm(&wasm)
+        .try_build()
+        .unwrap();
+    assert!(instance.run("move_every_zig").unwrap().verdict);
+    assert_eq!(b"Hello World!\n".to_vec(), instance.log());
+}
+
+#[test]
+fn test_with_wasm_rejects_text() {
+    let kvp = Kvp::default();
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    let result = Builder::new()
+        .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack))
+        .with_wasm(load_wat("log.wast"))
+        .try_build();
+    assert!(matches!(result, Err(Error::Vm(VmError::NotBinary))));
+}
+
+#[test]
+fn test_parse_error_location() {
+    let kvp = Kvp::default();
+    let mut pstack = Stk::default();
+    let mut rstack = Stk::default();
+    // the unknown instruction is on the fourth line
+    let script = "(module\n  (func $main (export \"move_every_zig\") (result i32)\n    i32.const 0\n    i32.bogus\n  )\n)";
+    let result = Builder::new()
+        .with_context(context(&kvp, &kvp, &mut pstack, &mut rstack))
+        .with_wat(script)
+        .try_build();
+    match result {
+        Err(Error::Vm(VmError::InvalidText(e))) => assert!(e.contains(":4:5"), "{e}"),
+        _ => panic!("expected a text parse error"),
+    }
+}
@@ -0,0 +1,100 @@
+// SPDX-License-Identifier: FSL-1.1
+#![cfg(feature = "wat")]
+mod common;
+
+use common::load_wast;
+use wacc::vm::{Diagnostic, Validator};
+
+#[test]
+fn test_valid_scripts() {
+    let validator = Validator::new().with_entry_point("move_every_zig");
+    for script in ["lock.wast", "pubkeysig_lock.wast", "preimage_lock.wast", "fork_lock.wast", "log.wast"] {
+        assert_eq!(Vec::<Diagnostic>::new(), validator.validate(&load_wast(script)), "{script}");
+    }
+    let validator = Validator::new().with_entry_point("for_great_justice");
+    assert!(validator.validate(&load_wast("unlock.wast")).is_empty());
+}
+
+#[test]
+fn test_signature_mismatch() {
+    // _check_signature declared with a single key-path like the signature_lock example
+    let script = r#"
+        (module
+          (import "wacc" "_check_signature" (func $check_signature (param i32 i32) (result i32)))
+          (func $main (export "move_zig") (param) (result i32)
+            i32.const 0
+            i32.const 7
+            call $check_signature
+          )
+          (memory (export "memory") 1)
+          (data (i32.const 0) "/pubkey")
+        )"#;
+    let diagnostics = Validator::new().with_entry_point("move_zig").validate(script.as_bytes());
+    assert_eq!(
+        vec![Diagnostic::SignatureMismatch {
+            name: "_check_signature".to_string(),
+            expected: "(i32, i32, i32, i32) -> (i32)".to_string(),
+            found: "(i32, i32) -> (i32)".to_string(),
+        }],
+        diagnostics
+    );
+}
+
+#[test]
+fn test_imports() {
+    let script = r#"
+        (module
+          (import "env" "abort" (func $abort))
+          (import "wacc" "_format_disk" (func $format (param i32 i32) (result i32)))
+          (import "wacc" "memory" (memory 1))
+          (func $main (export "move_every_zig") (param) (result i32)
+            i32.const 1
+          )
+        )"#;
+    let diagnostics = Validator::new().with_entry_point("move_every_zig").validate(script.as_bytes());
+    assert_eq!(
+        vec![
+            Diagnostic::ForeignImport { module: "env".to_string(), name: "abort".to_string() },
+            Diagnostic::UnknownFunction("_format_disk".to_string()),
+            Diagnostic::NonFunctionImport("memory".to_string()),
+            Diagnostic::MissingMemoryExport,
+        ],
+        diagnostics
+    );
+}
+
+#[test]
+fn test_entry_points() {
+    let script = r#"
+        (module
+          (func $main (export "move_every_zig") (param i32) (result i32)
+            local.get 0
+          )
+          (memory (export "memory") 1)
+        )"#;
+    let diagnostics = Validator::new()
+        .with_entry_point("move_every_zig")
+        .with_entry_point("for_great_justice")
+        .validate(script.as_bytes());
+    assert_eq!(
+        vec![
+            Diagnostic::EntryPointSignature {
+                name: "move_every_zig".to_string(),
+                found: "(i32) -> (i32)".to_string(),
+            },
+            Diagnostic::MissingEntryPoint("for_great_justice".to_string()),
+        ],
+        diagnostics
+    );
+}
+
+#[test]
+fn test_invalid_module() {
+    // not valid text
+    let diagnostics = Validator::new().validate(b"(module (func");
+    assert!(matches!(diagnostics.as_slice(), [Diagnostic::InvalidModule(_)]));
+
+    // the function doesn't return its result
+    let diagnostics = Validator::new().validate(b"(module (func $f (result i32)))");
+    assert!(matches!(diagnostics.as_slice(), [Diagnostic::InvalidModule(_)]));
+}
@@ -0,0 +1,163 @@
+// SPDX-License-Identifier: FSL-1.1
+#![cfg(feature = "wat")]
+mod common;
+
+use common::{load_wast, Kvp};
+use wacc::{error::VmError, storage::Pairs, vm::{MissingKey, Phase, Runtime, State, Value, Verifier}, Error};
+
+const PREIMAGE_HASH: &str = "16206b761d3b2e7675e088e337a82207b55711d3957efdb877a3d261b0ca2c38e201";
+
+fn pairs(hash: &str) -> (Kvp, Kvp) {
+    let mut kvp_unlock = Kvp::default();
+    let _ = kvp_unlock.put("/entry/proof", &"for great justice, move every zig!".to_string().into());
+    let mut kvp_lock = Kvp::default();
+    let _ = kvp_lock.put("/hash", &hex::decode(hash).unwrap().into());
+    (kvp_lock, kvp_unlock)
+}
+
+#[test]
+fn test_verifier_success() {
+    let (kvp_lock, kvp_unlock) = pairs(PREIMAGE_HASH);
+    let verification = Verifier::new()
+        .with_unlock(load_wast("preimage_unlock.wast"), "for_great_justice")
+        .with_lock(load_wast("preimage_lock.wast"), "move_every_zig")
+        .with_current(&kvp_lock)
+        .with_proposed(&kvp_unlock)
+        .verify()
+        .unwrap();
+    assert!(verification.verdict);
+    assert!(verification.pstack.is_empty());
+    assert_eq!(vec![Value::Success(0)], verification.rstack);
+    assert_eq!(1, verification.lock.unwrap().checks.len());
+}
+
+#[test]
+fn test_verifier_failure() {
+    let (kvp_lock, kvp_unlock) = pairs("16200000000000000000000000000000000000000000000000000000000000000000");
+    let verification = Verifier::new()
+        .with_unlock(load_wast("preimage_unlock.wast"), "for_great_justice")
+        .with_lock(load_wast("preimage_lock.wast"), "move_every_zig")
+        .with_current(&kvp_lock)
+        .with_proposed(&kvp_unlock)
+        .verify()
+        .unwrap();
+    assert!(!verification.verdict);
+    let lock = verification.lock.unwrap();
+    assert_eq!(Some("preimage doesn't match".to_string()), lock.checks[0].reason.clone());
+}
+
+#[test]
+fn test_verifier_shared_fuel() {
+    let (kvp_lock, kvp_unlock) = pairs(PREIMAGE_HASH);
+    let runtime = Runtime::builder().with_fuel().try_build().unwrap();
+    let verification = Verifier::new()
+        .with_runtime(&runtime)
+        .with_fuel(1_000_000)
+        .with_unlock(load_wast("preimage_unlock.wast"), "for_great_justice")
+        .with_lock(load_wast("preimage_lock.wast"), "move_every_zig")
+        .with_current(&kvp_lock)
+        .with_proposed(&kvp_unlock)
+        .verify()
+        .unwrap();
+    assert!(verification.verdict);
+
+    // the lock script starts with the fuel the unlock script left over
+    let unlock = verification.unlock.fuel.unwrap();
+    let lock = verification.lock.unwrap().fuel.unwrap();
+    assert_eq!(unlock.remaining, lock.budget);
+    assert!(lock.remaining < unlock.remaining);
+}
+
+#[test]
+fn test_verifier_out_of_fuel() {
+    let (kvp_lock, kvp_unlock) = pairs(PREIMAGE_HASH);
+    let result = Verifier::new()
+        .with_fuel(10)
+        .with_unlock(load_wast("preimage_unlock.wast"), "for_great_justice")
+        .with_lock(load_wast("preimage_lock.wast"), "move_every_zig")
+        .with_current(&kvp_lock)
+        .with_proposed(&kvp_unlock)
+        .verify();
+    assert!(matches!(result, Err(Error::Vm(VmError::OutOfFuel(10)))));
+}
+
+#[test]
+fn test_verifier_missing_state() {
+    let result = Verifier::new()
+        .with_unlock(load_wast("preimage_unlock.wast"), "for_great_justice")
+        .with_lock(load_wast("preimage_lock.wast"), "move_every_zig")
+        .verify();
+    assert!(matches!(result, Err(Error::Vm(VmError::MissingContext))));
+}
+
+#[test]
+fn test_verifier_preflight() {
+    let kvp_lock = Kvp::default();
+    let kvp_unlock = Kvp::default();
+    let verifier = Verifier::new()
+        .with_unlock(load_wast("preimage_unlock.wast"), "for_great_justice")
+        .with_lock(load_wast("preimage_lock.wast"), "move_every_zig")
+        .with_current(&kvp_lock)
+        .with_proposed(&kvp_unlock);
+    let proof = MissingKey {
+        phase: Phase::Unlock,
+        state: State::Proposed,
+        key: "/entry/proof".to_string(),
+        function: "_push".to_string(),
+    };
+    let hash = MissingKey {
+        phase: Phase::Lock,
+        state: State::Current,
+        key: "/hash".to_string(),
+        function: "_check_preimage".to_string(),
+    };
+    assert_eq!(vec![proof.clone(), hash], verifier.preflight().unwrap());
+
+    // the missing unlock key is reported before running the scripts
+    match verifier.with_preflight(true).verify() {
+        Err(Error::Vm(VmError::MissingKeys(missing))) => assert_eq!(vec![proof], missing),
+        _ => panic!("expected missing keys"),
+    }
+
+    // nothing is missing
+    let (kvp_lock, kvp_unlock) = pairs(PREIMAGE_HASH);
+    let verifier = Verifier::new()
+        .with_preflight(true)
+        .with_unlock(load_wast("preimage_unlock.wast"), "for_great_justice")
+        .with_lock(load_wast("preimage_lock.wast"), "move_every_zig")
+        .with_current(&kvp_lock)
+        .with_proposed(&kvp_unlock);
+    assert!(verifier.preflight().unwrap().is_empty());
+    assert!(verifier.verify().unwrap().verdict);
+}
+
+#[test]
+fn test_verifier_preflight_dedupes_keys() {
+    // the lock script checks the same key twice
+    let lock = br#"(module
+        (import "wacc" "_check_eq" (func $check_eq (param i32 i32) (result i32)))
+        (import "wacc" "_check_preimage" (func $check_preimage (param i32 i32) (result i32)))
+        (func $main (export "move_every_zig") (param) (result i32)
+            i32.const 0
+            i32.const 5
+            call $check_eq
+            drop
+            i32.const 0
+            i32.const 5
+            call $check_preimage
+            return
+        )
+        (memory (export "memory") 1)
+        (data (i32.const 0) "/hash")
+    )"#;
+    let (_, kvp_unlock) = pairs(PREIMAGE_HASH);
+    let kvp_lock = Kvp::default();
+    let verifier = Verifier::new()
+        .with_unlock(load_wast("preimage_unlock.wast"), "for_great_justice")
+        .with_lock(lock, "move_every_zig")
+        .with_current(&kvp_lock)
+        .with_proposed(&kvp_unlock);
+    let missing = verifier.preflight().unwrap();
+    assert_eq!(1, missing.len());
+    assert_eq!("/hash", missing[0].key);
+}